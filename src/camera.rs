@@ -0,0 +1,96 @@
+//! Camera setup, zoom and rotation controls.
+
+use bevy::{input::mouse::MouseWheel, prelude::*};
+use bevy_rapier2d::prelude::*;
+
+use crate::{Constants, Player};
+
+/// Zoom factor applied per scrolled line or per second of held key.
+const ZOOM_STEP: f32 = 1.1;
+/// How fast the projection catches up with the target zoom, per second.
+const ZOOM_SMOOTHING: f32 = 5.;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_camera)
+            .add_system(zoom_camera)
+            .add_system(rotate_camera);
+    }
+}
+
+/// Zoom chosen by the player, before the automatic zoom out.
+#[derive(Component)]
+struct Zoom(f32);
+
+fn setup_camera(mut commands: Commands) {
+    commands
+        .spawn_bundle(Camera2dBundle::default())
+        .insert(Zoom(1.));
+}
+
+/// Zoom with the mouse wheel and the +/- keys, and zoom out when going fast.
+fn zoom_camera(
+    constants: Res<Constants>,
+    time: Res<Time>,
+    keyboard_inputs: Res<Input<KeyCode>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    player: Query<&Velocity, With<Player>>,
+    mut camera: Query<(&mut Zoom, &mut OrthographicProjection)>,
+) {
+    // Positive steps zoom out, as a bigger scale shows more of the world.
+    let mut steps: f32 = mouse_wheel_events.iter().map(|event| -event.y).sum();
+    if keyboard_inputs.any_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        steps += time.delta_seconds();
+    }
+    if keyboard_inputs.any_pressed([KeyCode::Equals, KeyCode::NumpadAdd]) {
+        steps -= time.delta_seconds();
+    }
+
+    let clamp_zoom = |zoom: f32| zoom.max(constants.min_zoom).min(constants.max_zoom);
+
+    let auto_zoom = if constants.auto_zoom {
+        let speed = player
+            .get_single()
+            .map_or(0., |velocity| velocity.linvel.length());
+        1. + speed / constants.auto_zoom_speed
+    } else {
+        1.
+    };
+
+    for (mut zoom, mut projection) in &mut camera {
+        if steps != 0. {
+            zoom.0 = clamp_zoom(zoom.0 * ZOOM_STEP.powf(steps));
+        }
+
+        let target = clamp_zoom(zoom.0 * auto_zoom);
+        let smoothing = (ZOOM_SMOOTHING * time.delta_seconds()).min(1.);
+        projection.scale += (target - projection.scale) * smoothing;
+    }
+}
+
+/// Turn the camera toward the heading of the player, or back upright once disabled.
+fn rotate_camera(
+    constants: Res<Constants>,
+    time: Res<Time>,
+    player: Query<&Velocity, With<Player>>,
+    mut camera: Query<&mut Transform, With<Zoom>>,
+) {
+    let target = if constants.rotate_camera {
+        match player.get_single() {
+            Ok(velocity) if velocity.linvel.length() > constants.camera_rotation_min_speed => {
+                // The velocity points up once the camera is turned by its angle from the Y axis
+                Quat::from_rotation_z(Vec2::Y.angle_between(velocity.linvel))
+            }
+            _ => return,
+        }
+    } else {
+        Quat::IDENTITY
+    };
+
+    let smoothing = (constants.camera_rotation_rate * time.delta_seconds()).min(1.);
+    for mut transform in &mut camera {
+        transform.rotation = transform.rotation.slerp(target, smoothing);
+    }
+}
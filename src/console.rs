@@ -0,0 +1,208 @@
+//! Debug console, toggled with the backtick key.
+//!
+//! Commands:
+//! - `set <constant> <value>` changes a field of the `Constants`
+//! - `heat <amount>` sets the heat of the player
+//! - `reset` brings the player back to the spawn point, still and cold
+
+use bevy::{prelude::*, reflect::Struct};
+use bevy_inspector_egui::bevy_egui::{egui, EguiContext};
+use bevy_rapier2d::prelude::*;
+
+use crate::{Constants, Heat, Player, SpawnPoint};
+
+/// Lines kept in the output of the console.
+const OUTPUT_LINES: usize = 10;
+
+/// Constants that are a duration or a size, which cannot be negative.
+const NON_NEGATIVE_CONSTANTS: [&str; 15] = [
+    "force_ramp_secs",
+    "full_charge_secs",
+    "post_impulse_damping_secs",
+    "gravity_flip_secs",
+    "stop_duration",
+    "min_cooldown",
+    "max_cooldown",
+    "min_zoom",
+    "max_zoom",
+    "trail_size_scale",
+    "prediction_time",
+    "line_width",
+    "heat_decay_delay",
+    "vent_duration",
+    "parry_window",
+];
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>().add_system(run_console);
+    }
+}
+
+#[derive(Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    output: Vec<String>,
+}
+
+impl Console {
+    fn print(&mut self, line: String) {
+        self.output.push(line);
+        let excess = self.output.len().saturating_sub(OUTPUT_LINES);
+        self.output.drain(..excess);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Command<'a> {
+    Set { constant: &'a str, value: &'a str },
+    Heat(f32),
+    Reset,
+}
+
+fn parse(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        ["set", constant, value] => Ok(Command::Set { constant, value }),
+        ["heat", amount] => amount
+            .parse()
+            .map(Command::Heat)
+            .map_err(|_| format!("Invalid heat amount: {amount}")),
+        ["reset"] => Ok(Command::Reset),
+        ["spawn", kind] => Err(format!("Nothing to spawn as {kind} in this arena")),
+        _ => Err(format!("Unknown command: {line}")),
+    }
+}
+
+/// Parse `value` into the field of the constants named `constant`, whatever its type.
+fn set_constant(constants: &mut Constants, constant: &str, value: &str) -> Result<(), String> {
+    let field = constants
+        .field_mut(constant)
+        .ok_or_else(|| format!("Unknown constant: {constant}"))?;
+    let invalid = || format!("Invalid value for {constant}: {value}");
+
+    if let Some(field) = field.downcast_mut::<f32>() {
+        let parsed: f32 = value.parse().map_err(|_| invalid())?;
+        if !parsed.is_finite() {
+            return Err(invalid());
+        }
+        if parsed < 0. && NON_NEGATIVE_CONSTANTS.contains(&constant) {
+            return Err(format!("{constant} cannot be negative: {value}"));
+        }
+        *field = parsed;
+    } else if let Some(field) = field.downcast_mut::<bool>() {
+        *field = value.parse().map_err(|_| invalid())?;
+    } else if let Some(field) = field.downcast_mut::<usize>() {
+        *field = value.parse().map_err(|_| invalid())?;
+    } else {
+        return Err(format!("{constant} can only be set from the inspector"));
+    }
+    Ok(())
+}
+
+fn run_console(
+    mut egui_context: ResMut<EguiContext>,
+    keyboard_inputs: Res<Input<KeyCode>>,
+    mut console: ResMut<Console>,
+    mut constants: ResMut<Constants>,
+    spawn_point: Res<SpawnPoint>,
+    mut player: Query<(&mut Transform, &mut Velocity, &mut Heat), With<Player>>,
+) {
+    if keyboard_inputs.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+    }
+    if !console.open {
+        return;
+    }
+
+    let mut submitted = None;
+    egui::Window::new("Console").show(egui_context.ctx_mut(), |ui| {
+        for line in &console.output {
+            ui.monospace(line);
+        }
+        let response = ui.text_edit_singleline(&mut console.input);
+        if response.lost_focus() && ui.input().key_pressed(egui::Key::Enter) {
+            submitted = Some(std::mem::take(&mut console.input));
+        }
+        response.request_focus();
+    });
+
+    // The backtick toggling the console is typed in the input too
+    console.input.retain(|c| c != '`');
+    let line = match submitted {
+        Some(line) if !line.trim().is_empty() => line,
+        _ => return,
+    };
+
+    let result = parse(&line).and_then(|command| match command {
+        Command::Set { constant, value } => set_constant(&mut constants, constant, value),
+        Command::Heat(amount) => {
+            for (_, _, mut heat) in &mut player {
+                heat.amount = amount.clamp(0., 1.);
+                heat.pending = 0.;
+            }
+            Ok(())
+        }
+        Command::Reset => {
+            for (mut transform, mut velocity, mut heat) in &mut player {
+                transform.translation = spawn_point.0.extend(transform.translation.z);
+                *velocity = Velocity::zero();
+                *heat = Heat::default();
+            }
+            Ok(())
+        }
+    });
+
+    console.print(format!("> {line}"));
+    if let Err(error) = result {
+        console.print(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, set_constant, Command};
+    use crate::Constants;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("set impulse_value 2000"),
+            Ok(Command::Set {
+                constant: "impulse_value",
+                value: "2000"
+            })
+        );
+        assert_eq!(parse("  reset "), Ok(Command::Reset));
+        assert!(
+            matches!(parse("heat 0.5"), Ok(Command::Heat(amount)) if (amount - 0.5).abs() < f32::EPSILON)
+        );
+        assert!(parse("heat hot").is_err());
+        assert!(parse("spawn enemy").is_err());
+    }
+
+    #[test]
+    fn test_set_constant() {
+        let mut constants = Constants::default();
+
+        assert!(set_constant(&mut constants, "impulse_value", "2000").is_ok());
+        assert!((constants.impulse_value - 2000.).abs() < f32::EPSILON);
+        assert!(set_constant(&mut constants, "auto_zoom", "true").is_ok());
+        assert!(constants.auto_zoom);
+        assert!(set_constant(&mut constants, "trail_length", "5").is_ok());
+        assert_eq!(constants.trail_length, 5);
+
+        assert!(set_constant(&mut constants, "impulse_value", "fast").is_err());
+        assert!(set_constant(&mut constants, "impulse_value", "NaN").is_err());
+        assert!(set_constant(&mut constants, "impulse_value", "inf").is_err());
+        assert!((constants.impulse_value - 2000.).abs() < f32::EPSILON);
+        assert!(set_constant(&mut constants, "vent_duration", "-1").is_err());
+        assert!(set_constant(&mut constants, "line_width", "-2").is_err());
+        assert!(set_constant(&mut constants, "drift_boost_factor", "-1").is_ok());
+        assert!(set_constant(&mut constants, "unknown", "1").is_err());
+        assert!(set_constant(&mut constants, "drift", "1").is_err());
+    }
+}
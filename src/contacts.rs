@@ -0,0 +1,221 @@
+//! Process the collisions, and track the contacts of the player.
+//!
+//! Rapier's `CollisionEvent`s are only read by `process_collisions`,
+//! other systems consume the `GameplayCollision`s it sends.
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    cooldown::Cooldown,
+    menu::gameplay_running,
+    stop::{detect_stop, PlayerStop},
+    Player, WallMaterial,
+};
+
+/// Time allowed between two bounces to keep the combo going.
+const BOUNCE_COMBO_SECS: f32 = 1.5;
+
+pub struct ContactsPlugin;
+
+impl Plugin for ContactsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameplayCollision>()
+            .init_resource::<PlayerContacts>()
+            .init_resource::<BounceCombo>()
+            // Before the systems of `CoreStage::Update` which consume the collisions
+            .add_system_to_stage(CoreStage::PreUpdate, process_collisions)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                track_player_contacts.after(process_collisions),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                track_wall_contacts.after(process_collisions),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_bounce_combo
+                    .with_run_criteria(gameplay_running)
+                    .after(track_player_contacts)
+                    .after(detect_stop),
+            );
+    }
+}
+
+/// Kind of surface of a collider.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Surface {
+    Wall,
+    Body,
+    /// Detecting the bodies going through it, without a contact.
+    Sensor,
+    Unknown,
+}
+
+/// Collision between two entities, which started or stopped this frame.
+pub struct GameplayCollision {
+    pub entities: [Entity; 2],
+    pub surfaces: [Surface; 2],
+    pub started: bool,
+    /// Relative speed of the two entities.
+    pub impact_speed: f32,
+}
+
+/// Entities currently touching the player.
+///
+/// A sustained contact only appears once in `started`,
+/// until the player separates from the entity and collides with it again.
+#[derive(Default)]
+pub struct PlayerContacts {
+    /// Every entity in contact with the player.
+    pub touching: HashSet<Entity>,
+    /// Entities whose contact with the player started this frame.
+    pub started: Vec<Entity>,
+}
+
+/// Walls currently in contact with the entity.
+#[derive(Component, Default)]
+pub struct Touching {
+    walls: HashSet<Entity>,
+}
+
+impl Touching {
+    pub fn is_touching_wall(&self) -> bool {
+        !self.walls.is_empty()
+    }
+
+    pub fn walls(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.walls.iter().copied()
+    }
+}
+
+/// Consecutive bounces of the player, until it stops or its bounces are too far apart.
+pub struct BounceCombo {
+    pub count: u16,
+    window: Cooldown,
+}
+
+impl Default for BounceCombo {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            window: Cooldown::from_seconds(BOUNCE_COMBO_SECS),
+        }
+    }
+}
+
+fn process_collisions(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut gameplay_collisions: EventWriter<GameplayCollision>,
+    velocities: Query<&Velocity>,
+    walls: Query<(), With<WallMaterial>>,
+    sensors: Query<(), With<Sensor>>,
+) {
+    let surface = |entity| {
+        if sensors.contains(entity) {
+            Surface::Sensor
+        } else if walls.contains(entity) {
+            Surface::Wall
+        } else if velocities.contains(entity) {
+            Surface::Body
+        } else {
+            Surface::Unknown
+        }
+    };
+    let linvel = |entity| velocities.get(entity).map_or(Vec2::ZERO, |v| v.linvel);
+
+    for collision_event in collision_events.iter() {
+        let (a, b, started) = match *collision_event {
+            CollisionEvent::Started(a, b, _) => (a, b, true),
+            CollisionEvent::Stopped(a, b, _) => (a, b, false),
+        };
+        gameplay_collisions.send(GameplayCollision {
+            entities: [a, b],
+            surfaces: [surface(a), surface(b)],
+            started,
+            impact_speed: (linvel(a) - linvel(b)).length(),
+        });
+    }
+}
+
+fn track_player_contacts(
+    mut collisions: EventReader<GameplayCollision>,
+    mut contacts: ResMut<PlayerContacts>,
+    players: Query<(), With<Player>>,
+) {
+    contacts.started.clear();
+
+    for collision in collisions.iter() {
+        // Going through a sensor is not touching it
+        if collision.surfaces.contains(&Surface::Sensor) {
+            continue;
+        }
+        let [a, b] = collision.entities;
+        let other = match other_than_player(a, b, &players) {
+            Some(other) => other,
+            None => continue,
+        };
+
+        if !collision.started {
+            contacts.touching.remove(&other);
+        } else if contacts.touching.insert(other) {
+            contacts.started.push(other);
+        }
+    }
+}
+
+fn track_wall_contacts(
+    mut collisions: EventReader<GameplayCollision>,
+    mut touchings: Query<&mut Touching>,
+) {
+    for collision in collisions.iter() {
+        let [a, b] = collision.entities;
+        let [surface_a, surface_b] = collision.surfaces;
+
+        for (entity, other, other_surface) in [(a, b, surface_b), (b, a, surface_a)] {
+            if other_surface != Surface::Wall {
+                continue;
+            }
+            if let Ok(mut touching) = touchings.get_mut(entity) {
+                if collision.started {
+                    touching.walls.insert(other);
+                } else {
+                    touching.walls.remove(&other);
+                }
+            }
+        }
+    }
+}
+
+/// The entity colliding with the player, if the player is part of the collision.
+pub fn other_than_player(
+    a: Entity,
+    b: Entity,
+    players: &Query<(), With<Player>>,
+) -> Option<Entity> {
+    if players.contains(a) {
+        Some(b)
+    } else if players.contains(b) {
+        Some(a)
+    } else {
+        None
+    }
+}
+
+fn update_bounce_combo(
+    time: Res<Time>,
+    contacts: Res<PlayerContacts>,
+    stop: Res<PlayerStop>,
+    mut combo: ResMut<BounceCombo>,
+) {
+    combo.window.tick(time.delta());
+    let stopped = stop.is_stopped();
+
+    if !contacts.started.is_empty() && !stopped {
+        combo.count = combo.count.saturating_add(1);
+        combo.window.start();
+    } else if combo.count > 0 && (stopped || combo.window.finished()) {
+        combo.count = 0;
+    }
+}
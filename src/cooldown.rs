@@ -9,11 +9,21 @@ pub struct Cooldown {
     timer: Timer,
 }
 
+/// Negative and NaN seconds, which a `Duration` cannot hold, count as none.
+fn clamped_duration(seconds: f32) -> Duration {
+    if seconds.is_nan() {
+        Duration::ZERO
+    } else {
+        Duration::from_secs_f32(seconds.max(0.))
+    }
+}
+
 impl Cooldown {
     pub fn from_seconds(seconds: f32) -> Self {
-        let mut timer = Timer::from_seconds(seconds, false);
+        let duration = clamped_duration(seconds);
+        let mut timer = Timer::new(duration, false);
         // Start as available
-        timer.tick(Duration::from_secs_f32(seconds));
+        timer.tick(duration);
 
         Self { timer }
     }
@@ -22,6 +32,20 @@ impl Cooldown {
         self.timer.reset();
     }
 
+    /// Change the duration of the cooldown, taken into account from the next `start`.
+    pub fn set_duration(&mut self, seconds: f32) {
+        self.timer.set_duration(clamped_duration(seconds));
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.timer.duration()
+    }
+
+    /// Give back some of the remaining time, finishing the cooldown if it is all refunded.
+    pub fn refund(&mut self, seconds: f32) {
+        self.timer.tick(clamped_duration(seconds));
+    }
+
     pub fn tick(&mut self, delta: Duration) -> &Self {
         self.timer.tick(delta);
         self
@@ -30,6 +54,16 @@ impl Cooldown {
     pub fn finished(&self) -> bool {
         self.timer.finished()
     }
+
+    /// Fraction of the cooldown remaining, from 1 at `start` down to 0 once finished.
+    pub fn percent_left(&self) -> f32 {
+        self.timer.percent_left()
+    }
+
+    /// Fraction of the cooldown elapsed, from 0 at `start` up to 1 once finished.
+    pub fn progress(&self) -> f32 {
+        1. - self.percent_left()
+    }
 }
 
 #[cfg(test)]
@@ -57,4 +91,65 @@ mod tests {
         assert_eq!(cd.tick(Duration::from_secs_f32(0.75)).finished(), false);
         assert_eq!(cd.tick(Duration::from_secs_f32(0.75)).finished(), true);
     }
+
+    #[test]
+    fn test_cooldown_progress() {
+        let assert_complementary = |cd: &Cooldown| {
+            assert!((cd.progress() + cd.percent_left() - 1.).abs() < f32::EPSILON);
+        };
+
+        let mut cd = Cooldown::from_seconds(2.0);
+        assert_complementary(&cd);
+        assert!((cd.progress() - 1.).abs() < f32::EPSILON);
+
+        cd.start();
+        assert_complementary(&cd);
+        assert!(cd.progress().abs() < f32::EPSILON);
+
+        cd.tick(Duration::from_secs_f32(0.5));
+        assert_complementary(&cd);
+        assert!((cd.progress() - 0.25).abs() < f32::EPSILON);
+
+        cd.tick(Duration::from_secs_f32(1.));
+        assert_complementary(&cd);
+        assert!((cd.progress() - 0.75).abs() < f32::EPSILON);
+
+        cd.tick(Duration::from_secs_f32(10.));
+        assert_complementary(&cd);
+        assert!((cd.progress() - 1.).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_cooldown_refund() {
+        let mut cd = Cooldown::from_seconds(2.0);
+        cd.start();
+        cd.refund(0.5);
+        assert_eq!(cd.finished(), false);
+        assert_eq!(cd.tick(Duration::from_secs_f32(1.0)).finished(), false);
+
+        // Refunding more than remaining finishes immediately
+        cd.refund(10.0);
+        assert_eq!(cd.finished(), true);
+        assert!((cd.progress() - 1.).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_cooldown_negative_duration() {
+        let mut cd = Cooldown::from_seconds(-1.0);
+        assert_eq!(cd.finished(), true);
+
+        cd.set_duration(-1.0);
+        cd.start();
+        assert_eq!(cd.tick(Duration::ZERO).finished(), true);
+
+        cd.set_duration(f32::NAN);
+        cd.start();
+        assert_eq!(cd.tick(Duration::ZERO).finished(), true);
+
+        cd.set_duration(2.0);
+        cd.start();
+        cd.refund(-1.0);
+        cd.refund(f32::NAN);
+        assert_eq!(cd.tick(Duration::from_secs_f32(1.5)).finished(), false);
+    }
 }
@@ -0,0 +1,236 @@
+//! Debug visualisations, toggled from the inspector.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_inspector_egui::{
+    bevy_egui::{
+        egui::{
+            self,
+            plot::{Line, Plot, VLine, Value, Values},
+        },
+        EguiContext,
+    },
+    Inspectable, InspectorPlugin,
+};
+use bevy_prototype_lyon::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{apply_directional_drag, freeze_heat, trail::line_stroke, Constants, Heat, Player, Z};
+
+/// Length of the arrow heads.
+const ARROW_HEAD: f32 = 10.;
+/// Duration of the heat history kept, in seconds.
+const HEAT_HISTORY_SECS: f64 = 30.;
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InspectorPlugin::<DebugSettings>::new())
+            .add_startup_system(setup_force_arrows)
+            // Once every force of the frame has been applied
+            .add_system(update_force_arrows.after(apply_directional_drag))
+            .add_system(show_speed)
+            .init_resource::<HeatHistory>()
+            .add_system(record_heat.after(freeze_heat))
+            .add_system(show_heat_history.after(record_heat));
+    }
+}
+
+#[derive(Inspectable)]
+pub struct DebugSettings {
+    /// Draw the forces, impulses and velocity of the player.
+    show_forces: bool,
+    /// Display the speed of the player.
+    show_speed: bool,
+    /// Speed at which the readout turns fully red.
+    max_speed: f32,
+    /// Plot the heat of the player over the last seconds.
+    show_heat_history: bool,
+}
+
+impl Default for DebugSettings {
+    fn default() -> Self {
+        Self {
+            show_forces: false,
+            show_speed: false,
+            max_speed: 1500.,
+            show_heat_history: false,
+        }
+    }
+}
+
+/// Vector of the player drawn by an arrow.
+#[derive(Component, Clone, Copy)]
+enum ForceArrow {
+    Force,
+    Impulse,
+    Velocity,
+}
+
+impl ForceArrow {
+    const fn color(self) -> Color {
+        match self {
+            Self::Force => Color::GREEN,
+            Self::Impulse => Color::RED,
+            Self::Velocity => Color::CYAN,
+        }
+    }
+
+    /// Scale making the vector visible on screen.
+    const fn scale(self) -> f32 {
+        match self {
+            Self::Force => 0.1,
+            Self::Impulse => 0.05,
+            Self::Velocity => 0.2,
+        }
+    }
+}
+
+fn setup_force_arrows(mut commands: Commands, constants: Res<Constants>) {
+    for arrow in [ForceArrow::Force, ForceArrow::Impulse, ForceArrow::Velocity] {
+        commands
+            .spawn_bundle(GeometryBuilder::build_as(
+                &shapes::Line(Vec2::ZERO, Vec2::ZERO),
+                line_stroke(&constants, arrow.color(), constants.line_width),
+                Transform::from_xyz(0., 0., Z),
+            ))
+            .insert(Name::new("Force arrow"))
+            .insert(arrow);
+    }
+}
+
+fn update_force_arrows(
+    constants: Res<Constants>,
+    settings: Res<DebugSettings>,
+    player: Query<(&Transform, &Velocity, &ExternalForce, &ExternalImpulse), With<Player>>,
+    mut arrows: Query<(&ForceArrow, &mut Path, &mut DrawMode, &mut Visibility)>,
+) {
+    let player = player.get_single().ok();
+
+    for (arrow, mut path, mut draw_mode, mut visibility) in &mut arrows {
+        let (transform, velocity, ext_force, ext_impulse) = match player {
+            Some(player) if settings.show_forces => player,
+            _ => {
+                visibility.is_visible = false;
+                continue;
+            }
+        };
+        visibility.is_visible = true;
+
+        let vector = match arrow {
+            ForceArrow::Force => ext_force.force,
+            ForceArrow::Impulse => ext_impulse.impulse,
+            ForceArrow::Velocity => velocity.linvel,
+        };
+        *path = arrow_path(transform.translation.truncate(), vector * arrow.scale());
+        if constants.is_changed() {
+            *draw_mode = line_stroke(&constants, arrow.color(), constants.line_width);
+        }
+    }
+}
+
+fn arrow_path(start: Vec2, vector: Vec2) -> Path {
+    let end = start + vector;
+    let back = -vector.normalize_or_zero() * ARROW_HEAD;
+    let side = back.perp() * 0.5;
+
+    let mut builder = PathBuilder::new();
+    builder.move_to(start);
+    builder.line_to(end);
+    builder.move_to(end + back + side);
+    builder.line_to(end);
+    builder.line_to(end + back - side);
+    builder.build()
+}
+
+/// Readout of the speed of the player, from green when still to red at `max_speed`.
+fn show_speed(
+    settings: Res<DebugSettings>,
+    mut egui_context: ResMut<EguiContext>,
+    player: Query<&Velocity, With<Player>>,
+) {
+    if !settings.show_speed {
+        return;
+    }
+    let speed = match player.get_single() {
+        Ok(velocity) => velocity.linvel.length(),
+        Err(_) => return,
+    };
+    let ratio = (speed / settings.max_speed).min(1.);
+
+    egui::Window::new("Speed").show(egui_context.ctx_mut(), |ui| {
+        ui.colored_label(
+            egui::Rgba::from_rgb(ratio, 1. - ratio, 0.),
+            format!("{speed:.0} cm/s"),
+        );
+    });
+}
+
+/// Heat of the player over the last `HEAT_HISTORY_SECS`, with the times it overheated.
+#[derive(Default)]
+struct HeatHistory {
+    samples: VecDeque<(f64, f32)>,
+    overheats: VecDeque<f64>,
+    overheated: bool,
+}
+
+/// Record the heat every frame, even while hidden, so that the plot has a history once shown.
+fn record_heat(
+    time: Res<Time>,
+    mut history: ResMut<HeatHistory>,
+    player: Query<&Heat, With<Player>>,
+) {
+    let heat = match player.get_single() {
+        Ok(heat) => heat,
+        Err(_) => return,
+    };
+    let now = time.seconds_since_startup();
+
+    history.samples.push_back((now, heat.amount));
+    let overheated = heat.amount >= 1.;
+    if overheated && !history.overheated {
+        history.overheats.push_back(now);
+    }
+    history.overheated = overheated;
+
+    let oldest = now - HEAT_HISTORY_SECS;
+    while matches!(history.samples.front(), Some((at, _)) if *at < oldest) {
+        history.samples.pop_front();
+    }
+    while matches!(history.overheats.front(), Some(at) if *at < oldest) {
+        history.overheats.pop_front();
+    }
+}
+
+fn show_heat_history(
+    settings: Res<DebugSettings>,
+    time: Res<Time>,
+    history: Res<HeatHistory>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    if !settings.show_heat_history {
+        return;
+    }
+    // Seconds before now, so that the plot scrolls with time
+    let now = time.seconds_since_startup();
+    let points = history
+        .samples
+        .iter()
+        .map(|(at, amount)| Value::new(at - now, *amount));
+
+    egui::Window::new("Heat history").show(egui_context.ctx_mut(), |ui| {
+        Plot::new("Heat")
+            .height(150.)
+            .include_x(-HEAT_HISTORY_SECS)
+            .include_y(0.)
+            .include_y(1.)
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(Values::from_values_iter(points)).name("Heat"));
+                for overheat in &history.overheats {
+                    plot_ui.vline(VLine::new(overheat - now).color(egui::Color32::RED));
+                }
+            });
+    });
+}
@@ -0,0 +1,100 @@
+//! Difficulty increasing with the play time.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContext};
+
+use crate::{cooldown::Cooldown, menu::gameplay_running};
+
+/// Duration of each difficulty tier.
+const TIER_SECS: f32 = 30.;
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>()
+            .add_system(increase_difficulty.with_run_criteria(gameplay_running))
+            .add_system(show_difficulty);
+    }
+}
+
+/// Difficulty tier, raised every `TIER_SECS` of play.
+pub struct Difficulty {
+    pub tier: u16,
+    next_tier: Cooldown,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        let mut next_tier = Cooldown::from_seconds(TIER_SECS);
+        next_tier.start();
+
+        Self { tier: 0, next_tier }
+    }
+}
+
+impl Difficulty {
+    /// Multiplier of the heat gained, 10% more per tier.
+    pub fn heat_factor(&self) -> f32 {
+        0.1_f32.mul_add(f32::from(self.tier), 1.)
+    }
+
+    /// Multiplier of the cooldown durations, 5% less per tier down to a half.
+    pub fn cooldown_factor(&self) -> f32 {
+        0.05_f32.mul_add(-f32::from(self.tier), 1.).max(0.5)
+    }
+}
+
+fn increase_difficulty(time: Res<Time>, mut difficulty: ResMut<Difficulty>) {
+    if difficulty.next_tier.tick(time.delta()).finished() {
+        difficulty.next_tier.start();
+        difficulty.tier = difficulty.tier.saturating_add(1);
+    }
+}
+
+fn show_difficulty(mut egui_context: ResMut<EguiContext>, difficulty: Res<Difficulty>) {
+    egui::Window::new("Difficulty").show(egui_context.ctx_mut(), |ui| {
+        ui.label(format!("Tier {}", difficulty.tier));
+        ui.add(egui::ProgressBar::new(difficulty.next_tier.progress()).text("Next tier"));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::prelude::*;
+
+    use super::{increase_difficulty, Difficulty, TIER_SECS};
+    use crate::{
+        launch_pad::Countdown,
+        menu::{gameplay_running, PauseMenu},
+    };
+
+    #[test]
+    fn test_tier_frozen_while_paused() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<Difficulty>()
+            .init_resource::<PauseMenu>()
+            .init_resource::<Countdown>()
+            .add_system(increase_difficulty.with_run_criteria(gameplay_running));
+
+        let mut now = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        let mut step = |app: &mut App, secs: f32| {
+            now += Duration::from_secs_f32(secs);
+            app.world.resource_mut::<Time>().update_with_instant(now);
+            app.update();
+            app.world.resource::<Difficulty>().tier
+        };
+
+        app.world.resource_mut::<PauseMenu>().pause();
+        assert_eq!(step(&mut app, TIER_SECS + 1.), 0);
+
+        // The time spent paused does not count toward the next tier
+        *app.world.resource_mut::<PauseMenu>() = PauseMenu::default();
+        assert_eq!(step(&mut app, TIER_SECS * 0.5), 0);
+        assert_eq!(step(&mut app, TIER_SECS * 0.5 + 0.1), 1);
+    }
+}
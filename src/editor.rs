@@ -0,0 +1,98 @@
+//! Placement of obstacles with the mouse, to sketch arenas.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::{bevy_egui::EguiContext, Inspectable, InspectorPlugin};
+use bevy_rapier2d::prelude::*;
+
+use crate::{phase::wall_groups, Constants, WallMaterial, Z};
+
+/// Half extents of the placed obstacles.
+const OBSTACLE_HALF_SIZE: f32 = 25.;
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InspectorPlugin::<EditorSettings>::new())
+            .add_system(edit_obstacles);
+    }
+}
+
+#[derive(Inspectable, Default)]
+pub struct EditorSettings {
+    /// Left click places an obstacle, right click removes the nearest one.
+    place_obstacles: bool,
+}
+
+/// Obstacle placed in the editor, kept apart from the borders of the arena.
+#[derive(Component)]
+pub struct Obstacle;
+
+#[allow(clippy::too_many_arguments)] // The cursor needs the window and the camera
+fn edit_obstacles(
+    mut commands: Commands,
+    constants: Res<Constants>,
+    settings: Res<EditorSettings>,
+    mut egui_context: ResMut<EguiContext>,
+    mouse_inputs: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    camera: Query<(&GlobalTransform, &OrthographicProjection), With<Camera>>,
+    obstacles: Query<(Entity, &Transform), With<Obstacle>>,
+) {
+    // Clicks on the inspector windows are not meant for the world
+    if !settings.place_obstacles || egui_context.ctx_mut().wants_pointer_input() {
+        return;
+    }
+    let cursor = match cursor_world_position(&windows, &camera) {
+        Some(cursor) => cursor,
+        None => return,
+    };
+
+    if mouse_inputs.just_pressed(MouseButton::Left) {
+        commands
+            .spawn()
+            .insert(Name::new("Obstacle"))
+            .insert_bundle((
+                Obstacle,
+                Collider::cuboid(OBSTACLE_HALF_SIZE, OBSTACLE_HALF_SIZE),
+                constants.wall_collider_friction(),
+                Restitution::coefficient(0.9),
+                WallMaterial {
+                    color: Color::rgb(0.6, 0.8, 1.),
+                },
+                wall_groups(),
+            ))
+            .insert_bundle(TransformBundle::from(Transform::from_xyz(
+                cursor.x, cursor.y, Z,
+            )))
+            .insert_bundle(VisibilityBundle::default());
+    }
+
+    if mouse_inputs.just_pressed(MouseButton::Right) {
+        let nearest = obstacles.iter().min_by(|(_, a), (_, b)| {
+            let distance =
+                |transform: &Transform| transform.translation.truncate().distance_squared(cursor);
+            distance(a).total_cmp(&distance(b))
+        });
+        if let Some((obstacle, _)) = nearest {
+            commands.entity(obstacle).despawn_recursive();
+        }
+    }
+}
+
+/// Position of the cursor in the world, taking the camera position, zoom and rotation into account.
+fn cursor_world_position(
+    windows: &Windows,
+    camera: &Query<(&GlobalTransform, &OrthographicProjection), With<Camera>>,
+) -> Option<Vec2> {
+    let window = windows.get_primary()?;
+    let cursor = window.cursor_position()?;
+    let (camera_transform, projection) = camera.get_single().ok()?;
+
+    // The cursor position is from the bottom left while the camera is in the center
+    let resolution = Vec2::new(window.width(), window.height());
+    let offset = (cursor - resolution / 2.) * projection.scale;
+    let (_, rotation, translation) = camera_transform.to_scale_rotation_translation();
+
+    Some((translation + rotation * offset.extend(0.)).truncate())
+}
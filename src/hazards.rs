@@ -0,0 +1,198 @@
+//! Hazards of the arena, changing the rules for the player going through or hitting them.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, InspectorPlugin};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    apply_forces, clamp_impulse, contacts::GameplayCollision, cooldown::Cooldown,
+    menu::gameplay_running, push_off, Arena, Constants, Player, Z,
+};
+
+const GRAVITY_FLIP_RADIUS: f32 = 40.;
+const GRAVITY_FLIP_COLOR: Color = Color::PURPLE;
+const BUMPER_RADIUS: f32 = 20.;
+const BUMPER_COLOR: Color = Color::ORANGE;
+const BUMPER_FLASH_COLOR: Color = Color::WHITE;
+/// Time before a bumper can push the player again, so that it does not get stuck bouncing on it.
+const BUMPER_COOLDOWN_SECS: f32 = 0.3;
+
+pub struct HazardsPlugin;
+
+impl Plugin for HazardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InspectorPlugin::<HazardSettings>::new())
+            .init_resource::<DriftFlip>()
+            .add_startup_system(setup_hazards)
+            .add_system(flip_drift.with_run_criteria(gameplay_running))
+            // Adding to the impulses of the frame, before they are clamped
+            .add_system(
+                bump.with_run_criteria(gameplay_running)
+                    .after(apply_forces)
+                    .before(clamp_impulse),
+            )
+            .add_system(flash_bumpers.after(bump));
+    }
+}
+
+/// Read at startup, when the hazards are placed.
+#[derive(Inspectable, Default)]
+pub struct HazardSettings {
+    gravity_flip: bool,
+    bumpers: bool,
+}
+
+/// Sensor inverting the drift of the player entering it.
+#[derive(Component)]
+pub struct GravityFlip;
+
+/// Fixed ball pushing away the player hitting it, on a cooldown.
+#[derive(Component)]
+pub struct Bumper {
+    cooldown: Cooldown,
+}
+
+/// Inversion of the drift, reverted after `Constants::gravity_flip_secs`.
+pub struct DriftFlip {
+    active: bool,
+    duration: Cooldown,
+}
+
+impl Default for DriftFlip {
+    fn default() -> Self {
+        Self {
+            active: false,
+            // The duration is set from the constants when flipping
+            duration: Cooldown::from_seconds(0.),
+        }
+    }
+}
+
+impl DriftFlip {
+    /// The drift pulling the player, inverted while flipped.
+    pub fn apply(&self, drift: Vec2) -> Vec2 {
+        if self.active {
+            -drift
+        } else {
+            drift
+        }
+    }
+}
+
+fn setup_hazards(mut commands: Commands, settings: Res<HazardSettings>, arena: Res<Arena>) {
+    if settings.bumpers {
+        for side in [-1., 1.] {
+            let position = Vec2::new(side * arena.half_size.x * 0.5, arena.half_size.y * 0.3);
+            commands
+                .spawn()
+                .insert(Name::new("Bumper"))
+                .insert(Bumper {
+                    cooldown: Cooldown::from_seconds(BUMPER_COOLDOWN_SECS),
+                })
+                .insert_bundle((
+                    Collider::ball(BUMPER_RADIUS),
+                    Restitution::coefficient(1.),
+                    ColliderDebugColor(BUMPER_COLOR),
+                ))
+                .insert_bundle(TransformBundle::from(Transform::from_xyz(
+                    position.x, position.y, Z,
+                )));
+        }
+    }
+
+    if !settings.gravity_flip {
+        return;
+    }
+    let position = Vec2::new(0., -arena.half_size.y * 0.5);
+
+    commands
+        .spawn()
+        .insert(Name::new("Gravity flip"))
+        .insert(GravityFlip)
+        .insert_bundle((
+            Collider::ball(GRAVITY_FLIP_RADIUS),
+            Sensor,
+            ColliderDebugColor(GRAVITY_FLIP_COLOR),
+        ))
+        .insert_bundle(TransformBundle::from(Transform::from_xyz(
+            position.x, position.y, Z,
+        )));
+}
+
+/// Invert the drift when the player enters a gravity flip, restarting the duration if already flipped.
+fn flip_drift(
+    constants: Res<Constants>,
+    time: Res<Time>,
+    mut drift_flip: ResMut<DriftFlip>,
+    mut collisions: EventReader<GameplayCollision>,
+    players: Query<(), With<Player>>,
+    tiles: Query<(), With<GravityFlip>>,
+) {
+    for collision in collisions.iter() {
+        let [a, b] = collision.entities;
+        let entered = collision.started
+            && ((players.contains(a) && tiles.contains(b))
+                || (players.contains(b) && tiles.contains(a)));
+        if entered {
+            drift_flip.active = true;
+            drift_flip
+                .duration
+                .set_duration(constants.gravity_flip_secs);
+            drift_flip.duration.start();
+        }
+    }
+
+    drift_flip.duration.tick(time.delta());
+    if drift_flip.active && drift_flip.duration.finished() {
+        drift_flip.active = false;
+    }
+}
+
+/// Push the player away from the bumpers it hits, along the normal of the contact.
+fn bump(
+    constants: Res<Constants>,
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    mut collisions: EventReader<GameplayCollision>,
+    mut player: Query<(Entity, &Transform, &mut ExternalImpulse), With<Player>>,
+    mut bumpers: Query<(&mut Bumper, &Transform), Without<Player>>,
+) {
+    for (mut bumper, _) in &mut bumpers {
+        bumper.cooldown.tick(time.delta());
+    }
+    let (player, player_transform, mut ext_impulse) = match player.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    for collision in collisions.iter() {
+        let [a, b] = collision.entities;
+        let other = match (a == player, b == player) {
+            (true, _) => b,
+            (_, true) => a,
+            _ => continue,
+        };
+        let (mut bumper, bumper_transform) = match bumpers.get_mut(other) {
+            Ok(bumper) if collision.started && bumper.0.cooldown.finished() => bumper,
+            _ => continue,
+        };
+        bumper.cooldown.start();
+
+        // From the centers when the contact is already gone
+        let mut away = push_off(&rapier_context, player, other).normalize_or_zero();
+        if away == Vec2::ZERO {
+            away = (player_transform.translation - bumper_transform.translation)
+                .truncate()
+                .normalize_or_zero();
+        }
+        ext_impulse.impulse += away * constants.bumper_boost;
+    }
+}
+
+/// Flash the bumpers when they push, fading back over their cooldown.
+fn flash_bumpers(mut bumpers: Query<(&Bumper, &mut ColliderDebugColor)>) {
+    for (bumper, mut debug_color) in &mut bumpers {
+        let progress = bumper.cooldown.progress();
+        debug_color.0 = BUMPER_FLASH_COLOR * (1. - progress) + BUMPER_COLOR * progress;
+    }
+}
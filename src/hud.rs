@@ -0,0 +1,226 @@
+//! Heat and impulse cooldown displays, either as bars in the corner of the screen
+//! or as rings around the player.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::{
+    bevy_egui::{egui, EguiContext},
+    Inspectable, InspectorPlugin,
+};
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    apply_forces, cooldown::Cooldown, freeze_heat, heat_color, theme::Theme, Constants, Heat,
+    ImpulseCooldown, Player, PLAYER_RADIUS,
+};
+
+const HEAT_RING_RADIUS: f32 = PLAYER_RADIUS + 9.;
+const HEAT_RING_WIDTH: f32 = 4.;
+const COOLDOWN_RING_RADIUS: f32 = PLAYER_RADIUS + 15.;
+const COOLDOWN_RING_WIDTH: f32 = 2.;
+const COOLDOWN_RING_COLOR: Color = Color::rgba(1., 1., 1., 0.6);
+/// Duration of the flash of the cooldown ring once the impulse is ready, fading out.
+const READY_FLASH_SECS: f32 = 0.2;
+const BAR_WIDTH: f32 = 200.;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InspectorPlugin::<HudSettings>::new())
+            .add_system(attach_rings)
+            .add_system(update_heat_ring.after(freeze_heat))
+            .add_system(update_cooldown_ring.after(apply_forces))
+            .add_system(show_bars.after(freeze_heat).after(apply_forces));
+    }
+}
+
+/// Where the heat and the impulse cooldown of the player are displayed.
+#[derive(Inspectable, Clone, Copy, PartialEq, Eq)]
+pub enum HudMode {
+    /// Bars in the corner of the screen.
+    Screen,
+    /// Rings around the player, filling with the heat and as the cooldown recharges.
+    World,
+}
+
+impl Default for HudMode {
+    fn default() -> Self {
+        Self::Screen
+    }
+}
+
+#[derive(Inspectable, Default)]
+pub struct HudSettings {
+    mode: HudMode,
+}
+
+#[derive(Component)]
+struct HeatRing;
+
+#[derive(Component)]
+struct CooldownRing;
+
+fn attach_rings(mut commands: Commands, players: Query<Entity, Added<Player>>) {
+    for player in &players {
+        commands.entity(player).with_children(|parent| {
+            parent
+                .spawn_bundle(hidden_ring(HEAT_RING_RADIUS, HEAT_RING_WIDTH))
+                .insert(Name::new("Heat ring"))
+                .insert(HeatRing)
+                .insert(Visibility { is_visible: false });
+            parent
+                .spawn_bundle(hidden_ring(COOLDOWN_RING_RADIUS, COOLDOWN_RING_WIDTH))
+                .insert(Name::new("Cooldown ring"))
+                .insert(CooldownRing)
+                .insert(Visibility { is_visible: false });
+        });
+    }
+}
+
+/// Ring whose arc and color are set when it is shown.
+fn hidden_ring(radius: f32, width: f32) -> ShapeBundle {
+    GeometryBuilder::build_as(
+        &shapes::Circle {
+            radius,
+            center: Vec2::ZERO,
+        },
+        DrawMode::Stroke(StrokeMode::new(Color::NONE, width)),
+        Transform::default(),
+    )
+}
+
+/// Arc clockwise from the top of a ring, over the `fraction` of its circumference.
+fn arc(radius: f32, fraction: f32) -> Path {
+    let mut builder = PathBuilder::new();
+    builder.move_to(Vec2::new(0., radius));
+    builder.arc(
+        Vec2::ZERO,
+        Vec2::splat(radius),
+        -std::f32::consts::TAU * fraction.clamp(0., 1.),
+        0.,
+    );
+    builder.build()
+}
+
+/// Fill the ring clockwise from the top with the heat, in the color of the player.
+fn update_heat_ring(
+    settings: Res<HudSettings>,
+    constants: Res<Constants>,
+    player: Query<(&Heat, &Transform), With<Player>>,
+    mut rings: Query<
+        (&mut Path, &mut DrawMode, &mut Transform, &mut Visibility),
+        (With<HeatRing>, Without<Player>),
+    >,
+) {
+    let (heat, player_transform) = match player.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    for (mut path, mut draw_mode, mut transform, mut visibility) in &mut rings {
+        visibility.is_visible = settings.mode == HudMode::World && heat.amount > 0.;
+        if !visibility.is_visible {
+            continue;
+        }
+        // Keeping the top of the ring up while the player spins
+        transform.rotation = player_transform.rotation.inverse();
+
+        *path = arc(HEAT_RING_RADIUS, heat.amount);
+        *draw_mode = DrawMode::Stroke(StrokeMode::new(
+            heat_color(heat.amount, &constants),
+            HEAT_RING_WIDTH,
+        ));
+    }
+}
+
+/// Flash of the cooldown ring, started when the impulse gets ready.
+struct ReadyFlash {
+    flash: Cooldown,
+    was_ready: bool,
+}
+
+impl Default for ReadyFlash {
+    fn default() -> Self {
+        Self {
+            flash: Cooldown::from_seconds(READY_FLASH_SECS),
+            was_ready: true,
+        }
+    }
+}
+
+/// Fill the ring clockwise from the top as the impulse cooldown recharges, flashing once ready.
+fn update_cooldown_ring(
+    settings: Res<HudSettings>,
+    theme: Res<Theme>,
+    time: Res<Time>,
+    cooldown: Res<ImpulseCooldown>,
+    mut ready_flash: Local<ReadyFlash>,
+    player: Query<&Transform, With<Player>>,
+    mut rings: Query<
+        (&mut Path, &mut DrawMode, &mut Transform, &mut Visibility),
+        (With<CooldownRing>, Without<Player>),
+    >,
+) {
+    let ready = cooldown.0.finished();
+    if ready && !ready_flash.was_ready {
+        ready_flash.flash.start();
+    }
+    ready_flash.was_ready = ready;
+    ready_flash.flash.tick(time.delta());
+
+    let player_transform = match player.get_single() {
+        Ok(transform) => transform,
+        Err(_) => return,
+    };
+
+    for (mut path, mut draw_mode, mut transform, mut visibility) in &mut rings {
+        visibility.is_visible =
+            settings.mode == HudMode::World && !(ready && ready_flash.flash.finished());
+        if !visibility.is_visible {
+            continue;
+        }
+        // Keeping the top of the ring up while the player spins
+        transform.rotation = player_transform.rotation.inverse();
+
+        let (fraction, color) = if ready {
+            let mut color = theme.accent;
+            color.set_a(ready_flash.flash.percent_left());
+            (1., color)
+        } else {
+            (cooldown.0.progress(), COOLDOWN_RING_COLOR)
+        };
+        *path = arc(COOLDOWN_RING_RADIUS, fraction);
+        *draw_mode = DrawMode::Stroke(StrokeMode::new(color, COOLDOWN_RING_WIDTH));
+    }
+}
+
+fn show_bars(
+    settings: Res<HudSettings>,
+    cooldown: Res<ImpulseCooldown>,
+    mut egui_context: ResMut<EguiContext>,
+    player: Query<&Heat, With<Player>>,
+) {
+    if settings.mode != HudMode::Screen {
+        return;
+    }
+    let heat = match player.get_single() {
+        Ok(heat) => heat,
+        Err(_) => return,
+    };
+
+    egui::Area::new("Bars")
+        .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(10., -10.))
+        .show(egui_context.ctx_mut(), |ui| {
+            let recharge = cooldown.0.progress();
+            ui.add(
+                egui::ProgressBar::new(recharge)
+                    .desired_width(BAR_WIDTH)
+                    .text(if recharge < 1. { "Recharging" } else { "Ready" }),
+            );
+            ui.add(
+                egui::ProgressBar::new(heat.amount)
+                    .desired_width(BAR_WIDTH)
+                    .text(format!("Heat {:.0}%", heat.amount * 100.)),
+            );
+        });
+}
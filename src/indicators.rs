@@ -0,0 +1,224 @@
+//! Visual indicators attached to the player.
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    apply_forces,
+    cooldown::Cooldown,
+    inputs::{HeldDirections, ImpulseCharge, InputDenied},
+    launch, Constants, Player, PLAYER_RADIUS,
+};
+
+const CHARGE_RING_COLOR: Color = Color::YELLOW;
+/// Additional scale of the charge ring when fully charged.
+const CHARGE_RING_GROWTH: f32 = 0.6;
+const DENIED_RING_COLOR: Color = Color::RED;
+/// Duration of the flash of the denied ring, fading out.
+const DENIED_FLASH_SECS: f32 = 0.25;
+const LANDING_MARKER_COLOR: Color = Color::rgba(1., 1., 1., 0.2);
+/// Time step of the integration predicting where the player comes to rest.
+const PREDICTION_STEP_SECS: f32 = 1. / 60.;
+/// Most steps of a prediction, whatever `Constants::prediction_time`, to bound its cost per frame.
+const MAX_PREDICTION_STEPS: usize = 1200;
+/// Speed under which the player is considered at rest.
+const REST_SPEED: f32 = 5.;
+
+pub struct IndicatorsPlugin;
+
+impl Plugin for IndicatorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_landing_marker)
+            .add_system(attach_rings)
+            .add_system(update_charge_ring)
+            .add_system(update_landing_marker)
+            .add_system(flash_denied_ring.after(apply_forces));
+    }
+}
+
+#[derive(Component)]
+struct ChargeRing;
+
+/// Ring flashing when an input is ignored because of its cooldown.
+#[derive(Component)]
+struct DeniedRing;
+
+/// Where the player would come to rest after the impulse being charged.
+#[derive(Component)]
+struct LandingMarker;
+
+fn attach_rings(mut commands: Commands, players: Query<Entity, Added<Player>>) {
+    for player in &players {
+        commands.entity(player).with_children(|parent| {
+            parent
+                .spawn_bundle(ring(PLAYER_RADIUS + 5., CHARGE_RING_COLOR))
+                .insert(Name::new("Charge ring"))
+                .insert(ChargeRing);
+            parent
+                .spawn_bundle(ring(PLAYER_RADIUS + 2., DENIED_RING_COLOR))
+                .insert(Name::new("Denied ring"))
+                .insert(DeniedRing)
+                .insert(Visibility { is_visible: false });
+        });
+    }
+}
+
+fn ring(radius: f32, color: Color) -> ShapeBundle {
+    GeometryBuilder::build_as(
+        &shapes::Circle {
+            radius,
+            center: Vec2::ZERO,
+        },
+        DrawMode::Stroke(StrokeMode::new(color, 3.)),
+        Transform::default(),
+    )
+}
+
+/// Grow and brighten the ring with the impulse charge, hiding it once released.
+fn update_charge_ring(
+    charge: Res<ImpulseCharge>,
+    mut rings: Query<(&mut Transform, &mut DrawMode, &mut Visibility), With<ChargeRing>>,
+) {
+    for (mut transform, mut draw_mode, mut visibility) in &mut rings {
+        visibility.is_visible = charge.0 > 0.;
+        transform.scale = Vec3::splat(charge.0.mul_add(CHARGE_RING_GROWTH, 1.));
+
+        let mut color = CHARGE_RING_COLOR;
+        color.set_a(charge.0.mul_add(0.7, 0.3));
+        *draw_mode = DrawMode::Stroke(StrokeMode::new(color, 3.));
+    }
+}
+
+fn setup_landing_marker(mut commands: Commands) {
+    commands
+        .spawn_bundle(ring(PLAYER_RADIUS, LANDING_MARKER_COLOR))
+        .insert(Name::new("Landing marker"))
+        .insert(LandingMarker)
+        .insert(Visibility { is_visible: false });
+}
+
+/// Place the marker where the aimed impulse would leave the player, while it is charged.
+///
+/// Only the linear damping slows the prediction down: the walls and the drift are ignored.
+fn update_landing_marker(
+    constants: Res<Constants>,
+    charge: Res<ImpulseCharge>,
+    held: Res<HeldDirections>,
+    player: Query<(&Transform, &Velocity, &ReadMassProperties), With<Player>>,
+    mut marker: Query<(&mut Transform, &mut Visibility), (With<LandingMarker>, Without<Player>)>,
+) {
+    let (mut marker_transform, mut visibility) = match marker.get_single_mut() {
+        Ok(marker) => marker,
+        Err(_) => return,
+    };
+    let aim = held.combined();
+    let landing = match player.get_single() {
+        Ok((transform, velocity, mass_properties))
+            if constants.show_prediction
+                && charge.0 > 0.
+                && aim != Vec2::ZERO
+                && mass_properties.0.mass > 0. =>
+        {
+            let launch = launch(velocity.linvel, aim, charge.0, &constants);
+            let velocity = launch.velocity + launch.impulse / mass_properties.0.mass;
+            predict_rest(
+                transform.translation.truncate(),
+                velocity,
+                constants.base_linear_damping(),
+                prediction_steps(constants.prediction_time),
+            )
+        }
+        _ => None,
+    };
+
+    visibility.is_visible = landing.is_some();
+    if let Some(landing) = landing {
+        marker_transform.translation = landing.extend(marker_transform.translation.z);
+    }
+}
+
+/// Position at which a body slowed down by `damping` stops, integrated like the physics does.
+fn predict_rest(
+    mut position: Vec2,
+    mut velocity: Vec2,
+    damping: f32,
+    max_steps: usize,
+) -> Option<Vec2> {
+    for _ in 0..max_steps {
+        if velocity.length() < REST_SPEED {
+            return Some(position);
+        }
+        velocity /= PREDICTION_STEP_SECS.mul_add(damping, 1.);
+        position += velocity * PREDICTION_STEP_SECS;
+    }
+    None
+}
+
+/// Steps simulating `prediction_time` seconds, at most `MAX_PREDICTION_STEPS`.
+fn prediction_steps(prediction_time: f32) -> usize {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Clamped just below
+    let steps = (prediction_time.max(0.) / PREDICTION_STEP_SECS).ceil() as usize;
+    steps.min(MAX_PREDICTION_STEPS)
+}
+
+struct DeniedFlash(Cooldown);
+
+impl Default for DeniedFlash {
+    fn default() -> Self {
+        Self(Cooldown::from_seconds(DENIED_FLASH_SECS))
+    }
+}
+
+/// Flash the denied ring in red, fading out quickly.
+fn flash_denied_ring(
+    time: Res<Time>,
+    mut denied_events: EventReader<InputDenied>,
+    mut flash: Local<DeniedFlash>,
+    mut rings: Query<(&mut DrawMode, &mut Visibility), With<DeniedRing>>,
+) {
+    if denied_events.iter().count() > 0 {
+        flash.0.start();
+    }
+    flash.0.tick(time.delta());
+
+    for (mut draw_mode, mut visibility) in &mut rings {
+        visibility.is_visible = !flash.0.finished();
+
+        let mut color = DENIED_RING_COLOR;
+        color.set_a(flash.0.percent_left());
+        *draw_mode = DrawMode::Stroke(StrokeMode::new(color, 3.));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Vec2;
+
+    use super::{predict_rest, prediction_steps, MAX_PREDICTION_STEPS};
+
+    #[test]
+    fn test_predict_rest() {
+        assert_eq!(
+            predict_rest(Vec2::ONE, Vec2::ZERO, 1., 600),
+            Some(Vec2::ONE)
+        );
+
+        // Close to the continuous distance of `speed / damping`
+        let rest = predict_rest(Vec2::ZERO, Vec2::new(1000., 0.), 2., 600).expect("Rest position");
+        assert!((rest.x - 500.).abs() < 10., "{rest}");
+        assert!(rest.y.abs() < f32::EPSILON);
+
+        // Never stopping without damping
+        assert_eq!(predict_rest(Vec2::ZERO, Vec2::X * 1000., 0., 600), None);
+        // Not simulated long enough to stop
+        assert_eq!(predict_rest(Vec2::ZERO, Vec2::new(1000., 0.), 2., 60), None);
+    }
+
+    #[test]
+    fn test_prediction_steps() {
+        assert_eq!(prediction_steps(1.), 60);
+        assert_eq!(prediction_steps(-1.), 0);
+        assert_eq!(prediction_steps(1000.), MAX_PREDICTION_STEPS);
+    }
+}
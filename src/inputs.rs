@@ -1,78 +1,374 @@
+//! Read the devices into `InputEvent`s.
+//!
+//! The input events are sent by `translate_actions`: every system reading them
+//! runs after it, so that each event is seen by all of them within the same frame.
+//! The continuous force is not an event but the `HeldForce` state, also set by it.
+
 use bevy::prelude::*;
+use bevy_inspector_egui::{bevy_egui::EguiContext, Inspectable, InspectorPlugin};
+
+use crate::{
+    menu::{gameplay_running, PauseMenu},
+    Constants,
+};
 
 pub struct InputsPlugin;
 
 impl Plugin for InputsPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<InputEvent>()
-            .add_system(gamepad_system)
-            .add_system(keyboard_system)
-            // .add_system(mouse_system)
-            ;
+            .add_event::<InputDenied>()
+            .add_event::<Action>()
+            .add_plugin(InspectorPlugin::<InputSettings>::new())
+            .init_resource::<ImpulseCharge>()
+            .init_resource::<HeldDirections>()
+            .init_resource::<HeldForce>()
+            .add_system(
+                read_directions
+                    .before(gamepad_system)
+                    .before(keyboard_system),
+            )
+            .add_system(handle_gamepad_connections)
+            .add_system(gamepad_system.before(translate_actions))
+            .add_system(keyboard_system.before(translate_actions))
+            // .add_system(mouse_system.before(translate_actions))
+            .add_system(translate_actions.with_run_criteria(gameplay_running));
     }
 }
 
+/// What a device asks for, before being interpreted by the control scheme.
+#[derive(Clone, Copy)]
+enum Action {
+    /// The main action (impulse or thrust) starts being held.
+    MainPressed,
+    /// The main action is held while aiming.
+    MainHeld {
+        aim: Vec2,
+    },
+    /// The main action is released while aiming, with the charge built while holding it.
+    MainReleased {
+        aim: Vec2,
+        charge: f32,
+    },
+    /// A direction is held without the main action.
+    Move {
+        direction: Vec2,
+    },
+    Accelerate,
+    EmergencyStop,
+    PhaseShift,
+    Vent,
+}
+
 pub enum InputEvent {
-    Impulse { direction: Vec2 },
-    Force { direction: Vec2 },
+    Impulse {
+        direction: Vec2,
+        /// Charge of the impulse, from 0 for a tap to 1 when fully charged.
+        charge: f32,
+    },
+    /// Impulse along the current velocity.
+    BoostForward,
     Stabilisation,
+    /// The main action starting the stabilisation is released.
+    StabilisationReleased,
     Accelerate,
+    EmergencyStop,
+    /// Pass through the walls for a short time.
+    PhaseShift,
+    /// Cool down quickly, without launching impulses meanwhile.
+    Vent,
+}
+
+/// Direction of the force held this frame, zero when released.
+///
+/// Read as a state by the systems applying it over the frame, so that holding it
+/// pushes the same whatever the frame rate.
+#[derive(Default)]
+pub struct HeldForce(pub Vec2);
+
+/// An input ignored because its cooldown is not finished.
+pub struct InputDenied;
+
+/// Charge of the keyboard impulse, from 0 when released to 1 when fully charged.
+#[derive(Default)]
+pub struct ImpulseCharge(pub f32);
+
+/// How the main action moves the player.
+#[derive(Inspectable, Clone, Copy, PartialEq, Eq)]
+pub enum ControlScheme {
+    /// Releasing the action launches an impulse, on a cooldown.
+    Impulse,
+    /// Holding the action thrusts continuously in the aimed direction.
+    Thrust,
+}
+
+impl Default for ControlScheme {
+    fn default() -> Self {
+        Self::Impulse
+    }
+}
+
+/// Player preferences on how the inputs are read.
+#[derive(Inspectable, Default)]
+pub struct InputSettings {
+    /// Swap up and down for the keyboard and gamepad directions.
+    ///
+    /// The mouse aims at the cursor, so it is not affected.
+    invert_y: bool,
+    /// Tapping the stabilisation turns it on or off, instead of holding it.
+    pub toggle_stabilisation: bool,
+    control_scheme: ControlScheme,
+}
+
+impl InputSettings {
+    fn orient(&self, mut direction: Vec2) -> Vec2 {
+        if self.invert_y {
+            direction.y = -direction.y;
+        }
+        direction
+    }
+}
+
+/// Directions held on each device this frame.
+#[derive(Default)]
+pub struct HeldDirections {
+    keyboard: Vec2,
+    gamepad: Vec2,
+}
+
+impl HeldDirections {
+    /// Direction of every device together, so that one can move while the other aims.
+    pub fn combined(&self) -> Vec2 {
+        (self.keyboard + self.gamepad).normalize_or_zero()
+    }
+}
+
+fn read_directions(
+    settings: Res<InputSettings>,
+    keyboard_inputs: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut held: ResMut<HeldDirections>,
+) {
+    held.keyboard = keyboard_direction(&keyboard_inputs, &settings);
+    held.gamepad = gamepads
+        .iter()
+        .map(|gamepad| gamepad_direction(&axes, *gamepad, &settings))
+        .sum();
+}
+
+/// Log the gamepads coming and going, pausing the game when the last one is lost.
+///
+/// Every gamepad controls the single player, so there is no one to reassign.
+fn handle_gamepad_connections(
+    mut gamepad_events: EventReader<GamepadEvent>,
+    gamepads: Res<Gamepads>,
+    mut menu: ResMut<PauseMenu>,
+) {
+    for GamepadEvent {
+        gamepad,
+        event_type,
+    } in gamepad_events.iter()
+    {
+        match event_type {
+            GamepadEventType::Connected => info!("Gamepad {} connected", gamepad.id),
+            GamepadEventType::Disconnected => {
+                info!("Gamepad {} disconnected", gamepad.id);
+                if gamepads.iter().next().is_none() {
+                    menu.pause();
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 fn gamepad_system(
     gamepads: Res<Gamepads>,
     button_inputs: Res<Input<GamepadButton>>,
-    axes: Res<Axis<GamepadAxis>>,
-    mut input_events: EventWriter<InputEvent>,
+    button_axes: Res<Axis<GamepadButton>>,
+    held: Res<HeldDirections>,
+    mut actions: EventWriter<Action>,
 ) {
+    let aim = || held.combined();
+
     for gamepad in gamepads.iter().copied() {
         let south_button = GamepadButton::new(gamepad, GamepadButtonType::South);
+        let east_button = GamepadButton::new(gamepad, GamepadButtonType::East);
+        let west_button = GamepadButton::new(gamepad, GamepadButtonType::West);
+        let right_trigger = GamepadButton::new(gamepad, GamepadButtonType::RightTrigger2);
+
         if button_inputs.just_pressed(south_button) {
-            input_events.send(InputEvent::Stabilisation);
+            actions.send(Action::MainPressed);
+        }
+        if button_inputs.pressed(south_button) {
+            actions.send(Action::MainHeld { aim: aim() });
         }
         if button_inputs.just_released(south_button) {
-            dbg!("pressed south !");
-            let value_at = |axis| {
-                axes.get(GamepadAxis::new(gamepad, axis))
-                    .expect("Value at gamepad axis")
-            };
-
-            let x = value_at(GamepadAxisType::LeftStickX);
-            let y = value_at(GamepadAxisType::LeftStickY);
+            // Charged by how far the trigger is pressed instead of holding,
+            // a released trigger launching an uncharged impulse
+            let charge = button_axes.get(right_trigger).unwrap_or(0.).clamp(0., 1.);
+            actions.send(Action::MainReleased { aim: aim(), charge });
+        }
+        if button_inputs.just_pressed(east_button) {
+            actions.send(Action::PhaseShift);
+        }
+        if button_inputs.just_pressed(west_button) {
+            actions.send(Action::Vent);
+        }
+    }
+}
 
-            let direction = Vec2::new(x, y).normalize();
-            dbg!(direction);
+/// Held keyboard direction, building up while it is kept.
+#[derive(Default)]
+struct ForceRamp {
+    direction: Vec2,
+    main_held: bool,
+    held_secs: f32,
+}
 
-            input_events.send(InputEvent::Impulse { direction });
+impl ForceRamp {
+    /// Fraction of the force built up, all of it at once without a ramp duration.
+    fn progress(&self, ramp_secs: f32) -> f32 {
+        if ramp_secs <= 0. {
+            1.
+        } else {
+            (self.held_secs / ramp_secs).min(1.)
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)] // The charge and the force ramp are both tracked here
 fn keyboard_system(
+    constants: Res<Constants>,
+    time: Res<Time>,
+    settings: Res<InputSettings>,
     keyboard_inputs: Res<Input<KeyCode>>,
-    mut input_events: EventWriter<InputEvent>,
+    held: Res<HeldDirections>,
+    mut charge: ResMut<ImpulseCharge>,
+    mut force_ramp: Local<ForceRamp>,
+    mut actions: EventWriter<Action>,
+    egui_context: Option<ResMut<EguiContext>>,
 ) {
+    // The keys typed in the console are not controls
+    if keyboard_captured(egui_context) {
+        charge.0 = 0.;
+        return;
+    }
+    let main_held = keyboard_inputs.pressed(KeyCode::Space);
+    let direction = held.keyboard;
+    // Before being reset by the release
+    let released_charge = charge.0;
+
+    if settings.control_scheme == ControlScheme::Impulse && main_held {
+        charge.0 = (charge.0 + time.delta_seconds() / constants.full_charge_secs).min(1.);
+    } else {
+        charge.0 = 0.;
+    }
+
     if keyboard_inputs.just_pressed(KeyCode::A) {
-        input_events.send(InputEvent::Accelerate);
+        actions.send(Action::Accelerate);
+    }
+    if keyboard_inputs.just_pressed(KeyCode::X) {
+        actions.send(Action::EmergencyStop);
+    }
+    if keyboard_inputs.just_pressed(KeyCode::Z) {
+        actions.send(Action::PhaseShift);
+    }
+    if keyboard_inputs.just_pressed(KeyCode::V) {
+        actions.send(Action::Vent);
     }
     if keyboard_inputs.just_pressed(KeyCode::Space) {
-        input_events.send(InputEvent::Stabilisation);
+        actions.send(Action::MainPressed);
     }
     if keyboard_inputs.just_released(KeyCode::Space) {
-        let direction = keyboard_direction(&keyboard_inputs);
-        if direction != Vec2::ZERO {
-            input_events.send(InputEvent::Impulse { direction });
-        }
+        // Aiming with the gamepad while moving with the keyboard
+        actions.send(Action::MainReleased {
+            aim: held.combined(),
+            charge: released_charge,
+        });
     }
-    if !keyboard_inputs.pressed(KeyCode::Space) {
-        let direction = keyboard_direction(&keyboard_inputs);
-        if direction != Vec2::ZERO {
-            input_events.send(InputEvent::Force { direction });
+
+    if direction != force_ramp.direction || main_held != force_ramp.main_held {
+        *force_ramp = ForceRamp {
+            direction,
+            main_held,
+            held_secs: 0.,
+        };
+    }
+    force_ramp.held_secs += time.delta_seconds();
+    let progress = force_ramp.progress(constants.force_ramp_secs);
+
+    // Releasing the main action already launches an impulse, it should not push a force too
+    if direction == Vec2::ZERO || keyboard_inputs.just_released(KeyCode::Space) {
+        return;
+    }
+    if main_held {
+        // Aiming like the release, with the gamepad too
+        actions.send(Action::MainHeld {
+            aim: held.combined() * progress,
+        });
+    } else {
+        actions.send(Action::Move {
+            direction: direction * progress,
+        });
+    }
+}
+
+/// Whether a text field of the interface, like the console, takes the keyboard.
+/// Without the egui plugin, as in the tests, the keys always reach the game.
+pub fn keyboard_captured(egui_context: Option<ResMut<EguiContext>>) -> bool {
+    egui_context.map_or(false, |mut egui_context| {
+        egui_context.ctx_mut().wants_keyboard_input()
+    })
+}
+
+/// Translate the actions of every device into input events, depending on the control scheme.
+pub fn translate_actions(
+    constants: Res<Constants>,
+    settings: Res<InputSettings>,
+    mut actions: EventReader<Action>,
+    mut input_events: EventWriter<InputEvent>,
+    mut held_force: ResMut<HeldForce>,
+    egui_context: Option<ResMut<EguiContext>>,
+) {
+    let impulse_scheme = settings.control_scheme == ControlScheme::Impulse;
+    held_force.0 = Vec2::ZERO;
+    // Still read while typing in the console, not to be replayed once it is left
+    let typing = keyboard_captured(egui_context);
+
+    for action in actions.iter().filter(|_| !typing) {
+        // Ending the held stabilisation before the impulse launched by the release, if any
+        if impulse_scheme && matches!(action, Action::MainReleased { .. }) {
+            input_events.send(InputEvent::StabilisationReleased);
+        }
+        match *action {
+            Action::MainPressed if impulse_scheme => {
+                input_events.send(InputEvent::Stabilisation);
+            }
+            Action::MainReleased { aim, charge } if impulse_scheme && aim != Vec2::ZERO => {
+                input_events.send(InputEvent::Impulse {
+                    direction: aim,
+                    charge,
+                });
+            }
+            Action::MainReleased { .. } if impulse_scheme && constants.boost_forward => {
+                input_events.send(InputEvent::BoostForward);
+            }
+            // Thrusting in the aimed direction
+            Action::MainHeld { aim } if !impulse_scheme => held_force.0 = aim,
+            // Moving on its own only happens between impulses
+            Action::Move { direction } if impulse_scheme => held_force.0 = direction,
+            Action::Accelerate => input_events.send(InputEvent::Accelerate),
+            Action::EmergencyStop => input_events.send(InputEvent::EmergencyStop),
+            Action::PhaseShift => input_events.send(InputEvent::PhaseShift),
+            Action::Vent => input_events.send(InputEvent::Vent),
+            _ => {}
         }
     }
 }
 
-fn keyboard_direction(keyboard_inputs: &Input<KeyCode>) -> Vec2 {
+fn keyboard_direction(keyboard_inputs: &Input<KeyCode>, settings: &InputSettings) -> Vec2 {
     let mut direction = Vec2::ZERO;
     if keyboard_inputs.pressed(KeyCode::Up) {
         direction += Vec2::new(0., 1.);
@@ -86,7 +382,195 @@ fn keyboard_direction(keyboard_inputs: &Input<KeyCode>) -> Vec2 {
     if keyboard_inputs.pressed(KeyCode::Right) {
         direction += Vec2::new(1., 0.);
     }
-    direction.normalize_or_zero()
+    settings.orient(direction.normalize_or_zero())
+}
+
+fn gamepad_direction(axes: &Axis<GamepadAxis>, gamepad: Gamepad, settings: &InputSettings) -> Vec2 {
+    // Centered until the axis reports a value, as right after connecting
+    let value_at = |axis| axes.get(GamepadAxis::new(gamepad, axis)).unwrap_or(0.);
+    let x = value_at(GamepadAxisType::LeftStickX);
+    let y = value_at(GamepadAxisType::LeftStickY);
+
+    settings.orient(Vec2::new(x, y).normalize_or_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::{ecs::event::Events, prelude::*};
+
+    use super::{
+        keyboard_direction, keyboard_system, read_directions, translate_actions, Action, ForceRamp,
+        HeldDirections, HeldForce, ImpulseCharge, InputEvent, InputSettings,
+    };
+    use crate::Constants;
+
+    #[test]
+    fn test_keyboard_directions() {
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        let cases = [
+            (vec![KeyCode::Right], Vec2::X),
+            (vec![KeyCode::Up], Vec2::Y),
+            (vec![KeyCode::Left], -Vec2::X),
+            (vec![KeyCode::Down], -Vec2::Y),
+            (
+                vec![KeyCode::Up, KeyCode::Right],
+                Vec2::new(diagonal, diagonal),
+            ),
+            (
+                vec![KeyCode::Up, KeyCode::Left],
+                Vec2::new(-diagonal, diagonal),
+            ),
+            (
+                vec![KeyCode::Down, KeyCode::Left],
+                Vec2::new(-diagonal, -diagonal),
+            ),
+            (
+                vec![KeyCode::Down, KeyCode::Right],
+                Vec2::new(diagonal, -diagonal),
+            ),
+        ];
+
+        for (keys, expected) in cases {
+            let mut inputs = Input::<KeyCode>::default();
+            for key in &keys {
+                inputs.press(*key);
+            }
+            let direction = keyboard_direction(&inputs, &InputSettings::default());
+
+            assert!(
+                direction.abs_diff_eq(expected, 1e-6),
+                "{keys:?}: {direction} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_combined_directions() {
+        let held = HeldDirections {
+            keyboard: Vec2::X,
+            gamepad: Vec2::Y,
+        };
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        assert!(held
+            .combined()
+            .abs_diff_eq(Vec2::new(diagonal, diagonal), 1e-6));
+
+        // Opposite directions cancel out
+        let held = HeldDirections {
+            keyboard: Vec2::X,
+            gamepad: -Vec2::X,
+        };
+        assert_eq!(held.combined(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_force_ramp_progress() {
+        let ramp = ForceRamp {
+            direction: Vec2::X,
+            main_held: false,
+            held_secs: 0.25,
+        };
+        assert!((ramp.progress(1.) - 0.25).abs() < f32::EPSILON);
+        assert!((ramp.progress(0.1) - 1.).abs() < f32::EPSILON);
+
+        // Instant without a ramp, even before any time was held
+        let ramp = ForceRamp::default();
+        assert!((ramp.progress(0.) - 1.).abs() < f32::EPSILON);
+        assert!((ramp.progress(-1.) - 1.).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_release_sends_impulse_without_force() {
+        let mut app = App::new();
+        app.add_event::<Action>()
+            .add_event::<InputEvent>()
+            .init_resource::<Time>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Constants>()
+            .init_resource::<InputSettings>()
+            .init_resource::<ImpulseCharge>()
+            .init_resource::<HeldDirections>()
+            .init_resource::<Gamepads>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .init_resource::<HeldForce>()
+            .add_system(read_directions.before(keyboard_system))
+            .add_system(keyboard_system.before(translate_actions))
+            .add_system(translate_actions);
+
+        // The first update of the time has no delta, which would leave the force ramp at zero
+        app.world.resource_mut::<Time>().update();
+
+        let mut reader = app.world.resource::<Events<InputEvent>>().get_reader();
+        let mut step = |app: &mut App, key: KeyCode, pressed: bool| {
+            app.world.resource_mut::<Time>().update();
+            let mut inputs = app.world.resource_mut::<Input<KeyCode>>();
+            inputs.clear();
+            if pressed {
+                inputs.press(key);
+            } else {
+                inputs.release(key);
+            }
+            app.update();
+
+            let events = app.world.resource::<Events<InputEvent>>();
+            let impulses = reader
+                .iter(events)
+                .filter(|event| matches!(event, InputEvent::Impulse { .. }))
+                .count();
+            let forces = usize::from(app.world.resource::<HeldForce>().0 != Vec2::ZERO);
+            (impulses, forces)
+        };
+
+        assert_eq!(step(&mut app, KeyCode::Right, true), (0, 1));
+        assert_eq!(step(&mut app, KeyCode::Space, true), (0, 0));
+        assert_eq!(step(&mut app, KeyCode::Space, false), (1, 0));
+    }
+
+    /// Held force after holding a direction for `secs` at `fps` frames per second.
+    fn held_force_after(secs: f32, fps: f32) -> Vec2 {
+        let mut app = App::new();
+        app.add_event::<Action>()
+            .add_event::<InputEvent>()
+            .init_resource::<Time>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Constants>()
+            .init_resource::<InputSettings>()
+            .init_resource::<ImpulseCharge>()
+            .init_resource::<HeldDirections>()
+            .init_resource::<Gamepads>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .init_resource::<HeldForce>()
+            .add_system(read_directions.before(keyboard_system))
+            .add_system(keyboard_system.before(translate_actions))
+            .add_system(translate_actions);
+
+        let mut now = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::Right);
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // A few frames
+        let frames = (secs * fps).round() as u32;
+        for _ in 0..frames {
+            now += Duration::from_secs_f32(1. / fps);
+            app.world.resource_mut::<Time>().update_with_instant(now);
+            app.update();
+        }
+        app.world.resource::<HeldForce>().0
+    }
+
+    #[test]
+    fn test_held_force_independent_of_frame_rate() {
+        // Both ramping up, a sixth of a second being a whole number of frames at either rate
+        let slow = held_force_after(1. / 6., 30.);
+        let fast = held_force_after(1. / 6., 144.);
+
+        assert!(slow.length() < 1.);
+        assert!(slow.abs_diff_eq(fast, 1e-4), "{slow} != {fast}");
+    }
 }
 
 // fn mouse_system(
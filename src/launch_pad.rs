@@ -0,0 +1,121 @@
+//! Launch pad: a 3-2-1 countdown holding the player before the run starts.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::{
+    bevy_egui::{egui, EguiContext},
+    Inspectable, InspectorPlugin,
+};
+
+use crate::cooldown::Cooldown;
+
+const COUNTDOWN_SECS: f32 = 3.;
+
+pub struct LaunchPadPlugin;
+
+impl Plugin for LaunchPadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InspectorPlugin::<LaunchPadSettings>::new())
+            .init_resource::<Countdown>()
+            .add_startup_system(start_countdown)
+            .add_system(count_down)
+            .add_system(show_countdown.after(count_down));
+    }
+}
+
+/// Read at startup, when the countdown starts.
+#[derive(Inspectable)]
+pub struct LaunchPadSettings {
+    enabled: bool,
+}
+
+impl Default for LaunchPadSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// The player is held in place until it is over: as in the pause menu, the physics and
+/// the gameplay systems are paused, ignoring the inputs and freezing the cooldowns.
+pub struct Countdown {
+    delay: Cooldown,
+}
+
+impl Default for Countdown {
+    fn default() -> Self {
+        Self {
+            // Over unless started, as in the tests
+            delay: Cooldown::from_seconds(COUNTDOWN_SECS),
+        }
+    }
+}
+
+impl Countdown {
+    pub fn is_over(&self) -> bool {
+        self.delay.finished()
+    }
+
+    /// Whole seconds left, from 3 down to 1.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // A few seconds
+    fn seconds_left(&self) -> u8 {
+        (COUNTDOWN_SECS * self.delay.percent_left()).ceil() as u8
+    }
+}
+
+fn start_countdown(settings: Res<LaunchPadSettings>, mut countdown: ResMut<Countdown>) {
+    if settings.enabled {
+        countdown.delay.start();
+    }
+}
+
+fn count_down(time: Res<Time>, mut countdown: ResMut<Countdown>) {
+    if !countdown.is_over() {
+        countdown.delay.tick(time.delta());
+    }
+}
+
+fn show_countdown(mut egui_context: ResMut<EguiContext>, countdown: Res<Countdown>) {
+    if countdown.is_over() {
+        return;
+    }
+    egui::Window::new("Launch")
+        .title_bar(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.heading(countdown.seconds_left().to_string());
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::prelude::*;
+
+    use super::{count_down, Countdown, COUNTDOWN_SECS};
+
+    #[test]
+    fn test_count_down() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<Countdown>()
+            .add_system(count_down);
+        assert!(app.world.resource::<Countdown>().is_over());
+        app.world.resource_mut::<Countdown>().delay.start();
+
+        let mut now = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        let mut step = |app: &mut App, secs: f32| {
+            now += Duration::from_secs_f32(secs);
+            app.world.resource_mut::<Time>().update_with_instant(now);
+            app.update();
+            let countdown = app.world.resource::<Countdown>();
+            (countdown.is_over(), countdown.seconds_left())
+        };
+
+        assert_eq!(step(&mut app, 0.), (false, 3));
+        assert_eq!(step(&mut app, 1.5), (false, 2));
+        assert_eq!(step(&mut app, 1.), (false, 1));
+        assert_eq!(step(&mut app, COUNTDOWN_SECS), (true, 0));
+    }
+}
@@ -0,0 +1,83 @@
+//! Pause menu opened with Escape, quitting only once confirmed.
+
+use bevy::{app::AppExit, ecs::schedule::ShouldRun, prelude::*};
+use bevy_inspector_egui::bevy_egui::{egui, EguiContext};
+use bevy_rapier2d::prelude::*;
+
+use crate::{inputs::keyboard_captured, launch_pad::Countdown};
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenu>()
+            .add_system(toggle_pause_menu)
+            .add_system(show_pause_menu.after(toggle_pause_menu))
+            .add_system(pause_physics.after(show_pause_menu));
+    }
+}
+
+/// The physics and the gameplay systems are paused while the menu is open,
+/// as well as during the launch countdown.
+#[derive(Default)]
+pub struct PauseMenu {
+    open: bool,
+}
+
+impl PauseMenu {
+    pub fn pause(&mut self) {
+        self.open = true;
+    }
+}
+
+/// Run criterion of the gameplay systems, which stop while the game is paused or counting down:
+/// the inputs are not translated, and the heat and the cooldowns are frozen.
+pub fn gameplay_running(menu: Res<PauseMenu>, countdown: Res<Countdown>) -> ShouldRun {
+    if menu.open || !countdown.is_over() {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
+
+fn toggle_pause_menu(
+    keyboard_inputs: Res<Input<KeyCode>>,
+    mut menu: ResMut<PauseMenu>,
+    egui_context: Option<ResMut<EguiContext>>,
+) {
+    if keyboard_inputs.just_pressed(KeyCode::Escape) && !keyboard_captured(egui_context) {
+        menu.open = !menu.open;
+    }
+}
+
+fn show_pause_menu(
+    mut egui_context: ResMut<EguiContext>,
+    mut menu: ResMut<PauseMenu>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if !menu.open {
+        return;
+    }
+    egui::Window::new("Paused")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(egui_context.ctx_mut(), |ui| {
+            if ui.button("Resume").clicked() {
+                menu.open = false;
+            }
+            if ui.button("Quit").clicked() {
+                exit.send(AppExit);
+            }
+        });
+}
+
+fn pause_physics(
+    menu: Res<PauseMenu>,
+    countdown: Res<Countdown>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    if menu.is_changed() || countdown.is_changed() {
+        rapier_config.physics_pipeline_active = !menu.open && countdown.is_over();
+    }
+}
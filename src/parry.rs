@@ -0,0 +1,103 @@
+//! Parry: stabilising right before a body hits the player deflects it.
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    contacts::{GameplayCollision, Surface},
+    inputs::{translate_actions, InputEvent},
+    transfer_heat, Constants, Player,
+};
+
+pub struct ParryPlugin;
+
+impl Plugin for ParryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParryTiming>()
+            .add_event::<Parried>()
+            .add_system(track_stabilisation.after(translate_actions))
+            // The heat transfer skips the parried hits, before the window closes
+            .add_system(parry.after(transfer_heat).after(track_stabilisation));
+    }
+}
+
+/// Time of the last stabilisation, opening the parry window.
+#[derive(Default)]
+pub struct ParryTiming {
+    stabilised_at: Option<f64>,
+}
+
+impl ParryTiming {
+    /// Whether a hit happening at `now` is parried.
+    pub fn is_open(&self, now: f64, window: f32) -> bool {
+        self.stabilised_at.map_or(false, |stabilised_at| {
+            now - stabilised_at <= f64::from(window)
+        })
+    }
+}
+
+/// A hit parried by the player, at the position of the player.
+pub struct Parried {
+    pub position: Vec2,
+}
+
+fn track_stabilisation(
+    time: Res<Time>,
+    mut input_events: EventReader<InputEvent>,
+    mut timing: ResMut<ParryTiming>,
+) {
+    for input_event in input_events.iter() {
+        if matches!(input_event, InputEvent::Stabilisation) {
+            timing.stabilised_at = Some(time.seconds_since_startup());
+        }
+    }
+}
+
+/// Push away the bodies hitting the player within the parry window, closing it.
+pub fn parry(
+    constants: Res<Constants>,
+    time: Res<Time>,
+    mut timing: ResMut<ParryTiming>,
+    mut collisions: EventReader<GameplayCollision>,
+    player: Query<(Entity, &Transform), With<Player>>,
+    mut bodies: Query<(&Transform, &mut ExternalImpulse), Without<Player>>,
+    mut parried: EventWriter<Parried>,
+) {
+    let (player, player_transform) = match player.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let now = time.seconds_since_startup();
+
+    for collision in collisions.iter() {
+        if !collision.started || !timing.is_open(now, constants.parry_window) {
+            continue;
+        }
+        let [a, b] = collision.entities;
+        let [surface_a, surface_b] = collision.surfaces;
+        let (other, surface) = if a == player {
+            (b, surface_b)
+        } else if b == player {
+            (a, surface_a)
+        } else {
+            continue;
+        };
+        if surface != Surface::Body {
+            continue;
+        }
+        let (transform, mut ext_impulse) = match bodies.get_mut(other) {
+            Ok(body) => body,
+            Err(_) => continue,
+        };
+
+        let away = (transform.translation - player_transform.translation)
+            .truncate()
+            .normalize_or_zero();
+        ext_impulse.impulse += away * constants.parry_impulse;
+
+        timing.stabilised_at = None;
+        parried.send(Parried {
+            position: player_transform.translation.truncate(),
+        });
+    }
+}
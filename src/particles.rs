@@ -4,19 +4,174 @@ use bevy::{
 };
 
 use bevy_hanabi::*;
-use bevy_rapier2d::prelude::*;
+use bevy_inspector_egui::{Inspectable, InspectorPlugin};
+use bevy_rapier2d::prelude::{RapierContext, Velocity};
 
-use crate::{inputs::InputEvent, Player, PLAYER_RADIUS};
+use crate::{
+    apply_forces,
+    contacts::{BounceCombo, PlayerContacts, Touching},
+    cooldown::Cooldown,
+    hazards::GravityFlip,
+    inputs::{translate_actions, HeldForce, InputEvent},
+    menu::gameplay_running,
+    parry::{parry, Parried},
+    phase::PhaseShift,
+    targets::TargetBroken,
+    vent::Venting,
+    wall_push_off, Constants, DriftBoost, Heat, Player, WallMaterial, PLAYER_RADIUS,
+};
+
+/// Bounce combo at which the collision particles are the brightest.
+const MAX_BOUNCE_COMBO: f32 = 5.;
+
+/// Heat under which the player sparkles.
+const SPARKLE_HEAT_THRESHOLD: f32 = 0.1;
+
+/// Radius of the explosion of an uncharged impulse.
+const EXPLOSION_RADIUS: f32 = 25.;
+/// Additional radius of the explosion of a fully charged impulse, relative to `EXPLOSION_RADIUS`.
+const EXPLOSION_CHARGE_GROWTH: f32 = 2.;
+/// Speed of the particles of an explosion bursting in every direction.
+const EXPLOSION_SPEED: f32 = 200.;
+/// Initial speed of the particles of a directional explosion, before they are pushed away.
+const EXPLOSION_PUSHED_SPEED: f32 = 50.;
+/// Acceleration of the particles of a directional explosion, opposite to the impulse.
+const EXPLOSION_PUSH_ACCEL: f32 = 1200.;
+
+/// Interval between the bursts of the propulsor while the force is held.
+const PROPULSOR_BURST_SECS: f32 = 1. / 30.;
+
+/// Speed along the wall from which the player scrapes it.
+const SCRAPE_SPEED: f32 = 200.;
+/// Time in contact with a wall before it is scraped, so that the bounces do not throw sparks.
+const SCRAPE_DELAY_SECS: f32 = 0.1;
+
+/// Factor brightening the particles in the high-contrast mode.
+const HIGH_CONTRAST_BRIGHTNESS: f32 = 1.5;
+
+/// Zoom from which the effects are too small on screen to need their full detail.
+const LOW_DETAIL_ZOOM: f32 = 2.;
+/// Distance on screen from the center of the view, beyond which the effects use low detail.
+const LOW_DETAIL_DISTANCE: f32 = 800.;
 
 pub struct ParticleEffectPlugin;
 
 impl Plugin for ParticleEffectPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(create_wgpu_settings())
+            .add_plugin(InspectorPlugin::<ParticleConfig>::new())
             .add_plugin(HanabiPlugin)
+            .init_resource::<PoolCursors>()
             .add_startup_system(setup_particle_effects)
             .add_system(trigger_collision_effects)
-            .add_system(trigger_input_effects);
+            // Reading the events in the frame they are sent
+            .add_system(trigger_input_effects.after(translate_actions))
+            .add_system(
+                trigger_propulsor_effect
+                    .with_run_criteria(gameplay_running)
+                    .after(translate_actions),
+            )
+            .add_system(update_sparkle_effect)
+            .add_system(trigger_parry_effect.after(parry))
+            .add_system(trigger_target_effect)
+            .add_system(update_shimmer_effect)
+            .add_system(update_steam_effect)
+            .add_system(update_scrape_effect)
+            .add_system(trigger_drift_effect.after(apply_forces))
+            .add_system(attach_gravity_flip_effects)
+            .add_system(
+                select_effect_lods
+                    .after(trigger_collision_effects)
+                    .after(trigger_input_effects)
+                    .after(trigger_propulsor_effect)
+                    .after(update_sparkle_effect)
+                    .after(trigger_parry_effect)
+                    .after(trigger_target_effect)
+                    .after(update_shimmer_effect)
+                    .after(update_steam_effect)
+                    .after(update_scrape_effect)
+                    .after(trigger_drift_effect),
+            );
+    }
+}
+
+/// Amount of particles emitted by the effects.
+#[derive(Inspectable, Clone, Copy, Debug)]
+pub enum ParticleQuality {
+    Low,
+    Medium,
+    High,
+}
+
+impl ParticleQuality {
+    /// Factor applied to the number of particles of every effect.
+    const fn scale(self) -> f32 {
+        match self {
+            Self::Low => 0.25,
+            Self::Medium => 0.5,
+            Self::High => 1.,
+        }
+    }
+}
+
+/// Settings of the particle effects, read when they are created at startup.
+#[derive(Inspectable)]
+pub struct ParticleConfig {
+    pub quality: ParticleQuality,
+    /// Effects cycled through in each pool, so that simultaneous events each get their own burst.
+    pub collision_pool_size: usize,
+    pub explosion_pool_size: usize,
+    pub propulsor_pool_size: usize,
+    /// Radius reached by the explosion of a charged impulse, at most.
+    pub max_explosion_radius: f32,
+    /// Blast the explosion of an impulse opposite to it, instead of in every direction.
+    pub directional_explosion: bool,
+}
+
+impl Default for ParticleConfig {
+    fn default() -> Self {
+        Self {
+            quality: ParticleQuality::High,
+            collision_pool_size: 4,
+            explosion_pool_size: 3,
+            propulsor_pool_size: 2,
+            max_explosion_radius: 60.,
+            directional_explosion: true,
+        }
+    }
+}
+
+/// Level of detail of an effect, composing with the `ParticleQuality`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EffectLod {
+    Full,
+    Low,
+}
+
+impl EffectLod {
+    /// Factor applied to the number of particles, on top of the quality scale.
+    const fn scale(self) -> f32 {
+        match self {
+            Self::Full => 1.,
+            Self::Low => 0.2,
+        }
+    }
+}
+
+/// Assets of an effect for each level of detail.
+#[derive(Component)]
+struct EffectLods {
+    full: Handle<EffectAsset>,
+    low: Handle<EffectAsset>,
+    current: EffectLod,
+}
+
+impl EffectLods {
+    const fn handle(&self, lod: EffectLod) -> &Handle<EffectAsset> {
+        match lod {
+            EffectLod::Full => &self.full,
+            EffectLod::Low => &self.low,
+        }
     }
 }
 
@@ -28,60 +183,240 @@ fn create_wgpu_settings() -> WgpuSettings {
     options
 }
 
+/// Effects frequent enough to be triggered several times in a frame.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EffectPool {
+    Collision,
+    Explosion,
+    Propulsor,
+}
+
+/// Effect of a pool, with its index in it.
 #[derive(Component)]
-struct ExplosionEffect;
+struct Pooled {
+    pool: EffectPool,
+    index: usize,
+}
+
+/// Tint the assets of a collision effect were built with, so that they are only rebuilt when it changes.
+#[derive(Component, Clone, Copy, PartialEq)]
+struct CollisionTint {
+    color: Color,
+    brightness: f32,
+}
+
+/// Next effect of each pool, cycling round-robin to reuse the oldest burst
+/// when there are more events than effects.
+#[derive(Default)]
+struct PoolCursors {
+    collision: usize,
+    explosion: usize,
+    propulsor: usize,
+}
+
+impl PoolCursors {
+    fn next(&mut self, pool: EffectPool, config: &ParticleConfig) -> Option<usize> {
+        let (cursor, size) = match pool {
+            EffectPool::Collision => (&mut self.collision, config.collision_pool_size),
+            EffectPool::Explosion => (&mut self.explosion, config.explosion_pool_size),
+            EffectPool::Propulsor => (&mut self.propulsor, config.propulsor_pool_size),
+        };
+        let index = cursor.checked_rem(size)?;
+        *cursor = index + 1;
+        Some(index)
+    }
+}
 
 #[derive(Component)]
-struct CollisionEffect;
+struct SparkleEffect;
 
 #[derive(Component)]
-pub struct PropulsorEffect;
+struct BrakeEffect;
 
-fn setup_particle_effects(mut commands: Commands, mut effects: ResMut<Assets<EffectAsset>>) {
+#[derive(Component)]
+struct ParryEffect;
+
+#[derive(Component)]
+struct TargetEffect;
+
+#[derive(Component)]
+struct ShimmerEffect;
+
+#[derive(Component)]
+struct SteamEffect;
+
+#[derive(Component)]
+struct ScrapeEffect;
+
+#[derive(Component)]
+struct DriftEffect;
+
+#[derive(Component)]
+struct GravityFlipEffect;
+
+fn setup_particle_effects(
+    mut commands: Commands,
+    mut effects: ResMut<Assets<EffectAsset>>,
+    config: Res<ParticleConfig>,
+    constants: Res<Constants>,
+) {
+    let scale = config.quality.scale();
+    let brightness = particle_brightness(&constants);
+
+    for index in 0..config.collision_pool_size {
+        let tint = CollisionTint {
+            color: Color::GRAY,
+            brightness,
+        };
+        let effect = spawn_particle_effect(
+            &mut commands,
+            &mut effects,
+            "Collision effect",
+            Pooled {
+                pool: EffectPool::Collision,
+                index,
+            },
+            |lod_scale| collision_effect(scale * lod_scale, tint.brightness, tint.color),
+        );
+        commands.entity(effect).insert(tint);
+    }
+    for index in 0..config.explosion_pool_size {
+        spawn_particle_effect(
+            &mut commands,
+            &mut effects,
+            "Explosion effect",
+            Pooled {
+                pool: EffectPool::Explosion,
+                index,
+            },
+            |lod_scale| {
+                explosion_effect(scale * lod_scale, brightness, EXPLOSION_RADIUS, Vec2::ZERO)
+            },
+        );
+    }
+    for index in 0..config.propulsor_pool_size {
+        spawn_particle_effect(
+            &mut commands,
+            &mut effects,
+            "Propulsor effect",
+            Pooled {
+                pool: EffectPool::Propulsor,
+                index,
+            },
+            |lod_scale| propulsor_effect(scale * lod_scale, brightness),
+        );
+    }
+    spawn_particle_effect(
+        &mut commands,
+        &mut effects,
+        "Sparkle effect",
+        SparkleEffect,
+        |lod_scale| sparkle_effect(scale * lod_scale, brightness),
+    );
     spawn_particle_effect(
         &mut commands,
         &mut effects,
-        "Collision effect",
-        CollisionEffect,
-        collision_effect(),
+        "Brake effect",
+        BrakeEffect,
+        |lod_scale| brake_effect(scale * lod_scale, brightness),
     );
     spawn_particle_effect(
         &mut commands,
         &mut effects,
-        "Explosion effect",
-        ExplosionEffect,
-        explosion_effect(),
+        "Parry effect",
+        ParryEffect,
+        |lod_scale| parry_effect(scale * lod_scale, brightness),
     );
     spawn_particle_effect(
         &mut commands,
         &mut effects,
-        "Propulsor effect",
-        PropulsorEffect,
-        propulsor_effect(),
+        "Target effect",
+        TargetEffect,
+        |lod_scale| target_effect(scale * lod_scale, brightness),
+    );
+    spawn_particle_effect(
+        &mut commands,
+        &mut effects,
+        "Shimmer effect",
+        ShimmerEffect,
+        |lod_scale| shimmer_effect(scale * lod_scale, brightness),
+    );
+    spawn_particle_effect(
+        &mut commands,
+        &mut effects,
+        "Steam effect",
+        SteamEffect,
+        |lod_scale| steam_effect(scale * lod_scale, brightness),
+    );
+    spawn_particle_effect(
+        &mut commands,
+        &mut effects,
+        "Scrape effect",
+        ScrapeEffect,
+        |lod_scale| scrape_effect(scale * lod_scale, brightness),
+    );
+    spawn_particle_effect(
+        &mut commands,
+        &mut effects,
+        "Drift effect",
+        DriftEffect,
+        |lod_scale| drift_effect(scale * lod_scale, brightness),
     );
 }
 
+/// Spawn an effect with its variant for every level of detail, built from the scale of its particles.
 fn spawn_particle_effect(
     commands: &mut Commands,
     effects: &mut ResMut<Assets<EffectAsset>>,
     name: &'static str,
     tag: impl Component,
-    effect: EffectAsset,
-) {
+    build_effect: impl Fn(f32) -> EffectAsset,
+) -> Entity {
+    let effect = build_effect(EffectLod::Full.scale());
     let spawner = effect.spawner;
+    let handle = effects.add(effect);
+    let lods = EffectLods {
+        full: handle.clone(),
+        low: effects.add(build_effect(EffectLod::Low.scale())),
+        current: EffectLod::Full,
+    };
     commands
         .spawn()
         .insert(tag)
         .insert(Name::new(name))
-        .insert_bundle(ParticleEffectBundle::new(effects.add(effect)).with_spawner(spawner));
+        .insert(lods)
+        .insert_bundle(ParticleEffectBundle::new(handle).with_spawner(spawner))
+        .id()
+}
+
+/// Brightness of the particles, raised in the high-contrast mode.
+fn particle_brightness(constants: &Constants) -> f32 {
+    if constants.high_contrast {
+        HIGH_CONTRAST_BRIGHTNESS
+    } else {
+        1.
+    }
+}
+
+/// Key of a gradient, brighter and more opaque by `brightness`, the transparent keys staying so.
+fn brighten(color: Color, brightness: f32) -> Vec4 {
+    (Vec4::from(color) * brightness).min(Vec4::ONE)
 }
 
-fn collision_effect() -> EffectAsset {
+/// Move the effect to `position` and restart its burst, if it has a spawner.
+fn trigger_effect_at(effect: &mut ParticleEffect, transform: &mut Transform, position: Vec3) {
+    transform.translation = position;
+    if let Some(spawner) = effect.maybe_spawner() {
+        spawner.reset();
+    }
+}
+
+fn collision_effect(scale: f32, brightness: f32, color: Color) -> EffectAsset {
     let mut gradient = Gradient::new();
-    gradient.add_key(0., Color::GRAY.into());
-    gradient.add_key(1., Color::BLACK.into());
+    gradient.add_key(0., brighten(color, brightness));
+    gradient.add_key(1., brighten(Color::BLACK, brightness));
 
-    let spawner = Spawner::once(15.0.into(), false);
+    let spawner = Spawner::once((15.0 * scale).into(), false);
 
     EffectAsset {
         name: "Impact".into(),
@@ -102,12 +437,20 @@ fn collision_effect() -> EffectAsset {
     .render(ColorOverLifetimeModifier { gradient })
 }
 
-fn explosion_effect() -> EffectAsset {
+/// Explosion whose particles keep the same density as the radius grows.
+///
+/// The particles start slowly and are blasted along `push`, or burst radially when it is zero.
+fn explosion_effect(scale: f32, brightness: f32, radius: f32, push: Vec2) -> EffectAsset {
     let mut gradient = Gradient::new();
-    gradient.add_key(0., Color::rgba(1., 1., 0., 1.).into());
-    gradient.add_key(1., Color::rgba(1., 0., 0., 0.).into());
+    gradient.add_key(0., brighten(Color::rgba(1., 1., 0., 1.), brightness));
+    gradient.add_key(1., brighten(Color::rgba(1., 0., 0., 0.), brightness));
 
-    let spawner = Spawner::once(100.0.into(), false);
+    let spawner = Spawner::once((100.0 * scale * radius / EXPLOSION_RADIUS).into(), false);
+    let speed = if push == Vec2::ZERO {
+        EXPLOSION_SPEED
+    } else {
+        EXPLOSION_PUSHED_SPEED
+    };
 
     EffectAsset {
         name: "Explosion".into(),
@@ -116,24 +459,27 @@ fn explosion_effect() -> EffectAsset {
         ..default()
     }
     .init(PositionSphereModifier {
-        radius: 25.,
-        speed: 200.0.into(),
+        radius,
+        speed: speed.into(),
         dimension: ShapeDimension::Surface,
         ..default()
     })
     .init(ParticleLifetimeModifier { lifetime: 0.5 })
+    .update(AccelModifier {
+        accel: (push * EXPLOSION_PUSH_ACCEL).extend(0.),
+    })
     .render(SizeOverLifetimeModifier {
         gradient: Gradient::constant(Vec2::splat(5.)),
     })
     .render(ColorOverLifetimeModifier { gradient })
 }
 
-fn propulsor_effect() -> EffectAsset {
+fn propulsor_effect(scale: f32, brightness: f32) -> EffectAsset {
     let mut gradient = Gradient::new();
-    gradient.add_key(0., Color::rgba(1., 1., 0., 1.).into());
-    gradient.add_key(1., Color::rgba(1., 0., 0., 0.).into());
+    gradient.add_key(0., brighten(Color::rgba(1., 1., 0., 1.), brightness));
+    gradient.add_key(1., brighten(Color::rgba(1., 0., 0., 0.), brightness));
 
-    let spawner = Spawner::once(20.0.into(), false);
+    let spawner = Spawner::once((20.0 * scale).into(), false);
 
     EffectAsset {
         name: "Propulsor".into(),
@@ -154,73 +500,778 @@ fn propulsor_effect() -> EffectAsset {
     .render(ColorOverLifetimeModifier { gradient })
 }
 
+fn sparkle_effect(scale: f32, brightness: f32) -> EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0., brighten(Color::rgba(0.6, 0.9, 1., 1.), brightness));
+    gradient.add_key(1., brighten(Color::rgba(0.6, 0.9, 1., 0.), brightness));
+
+    let spawner = Spawner::rate((6.0 * scale).into()).with_active(false);
+
+    EffectAsset {
+        name: "Sparkle".into(),
+        capacity: 1024,
+        spawner,
+        ..default()
+    }
+    .init(PositionSphereModifier {
+        radius: PLAYER_RADIUS,
+        speed: 5.0.into(),
+        dimension: ShapeDimension::Surface,
+        ..default()
+    })
+    .init(ParticleLifetimeModifier { lifetime: 0.8 })
+    .render(SizeOverLifetimeModifier {
+        gradient: Gradient::constant(Vec2::splat(2.)),
+    })
+    .render(ColorOverLifetimeModifier { gradient })
+}
+
+fn brake_effect(scale: f32, brightness: f32) -> EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0., brighten(Color::rgba(0.8, 0.9, 1., 1.), brightness));
+    gradient.add_key(1., brighten(Color::rgba(0., 0.3, 1., 0.), brightness));
+
+    let spawner = Spawner::once((60.0 * scale).into(), false);
+
+    EffectAsset {
+        name: "Brake".into(),
+        capacity: 32768,
+        spawner,
+        ..default()
+    }
+    .init(PositionSphereModifier {
+        radius: PLAYER_RADIUS,
+        speed: 80.0.into(),
+        dimension: ShapeDimension::Surface,
+        ..default()
+    })
+    .init(ParticleLifetimeModifier { lifetime: 0.4 })
+    .render(SizeOverLifetimeModifier {
+        gradient: Gradient::constant(Vec2::splat(3.)),
+    })
+    .render(ColorOverLifetimeModifier { gradient })
+}
+
+fn parry_effect(scale: f32, brightness: f32) -> EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0., brighten(Color::WHITE, brightness));
+    gradient.add_key(1., brighten(Color::rgba(0.4, 1., 1., 0.), brightness));
+
+    let spawner = Spawner::once((80.0 * scale).into(), false);
+
+    EffectAsset {
+        name: "Parry".into(),
+        capacity: 32768,
+        spawner,
+        ..default()
+    }
+    .init(PositionSphereModifier {
+        radius: PLAYER_RADIUS,
+        speed: 300.0.into(),
+        dimension: ShapeDimension::Surface,
+        ..default()
+    })
+    .init(ParticleLifetimeModifier { lifetime: 0.3 })
+    .render(SizeOverLifetimeModifier {
+        gradient: Gradient::constant(Vec2::splat(4.)),
+    })
+    .render(ColorOverLifetimeModifier { gradient })
+}
+
+fn target_effect(scale: f32, brightness: f32) -> EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0., brighten(Color::GOLD, brightness));
+    gradient.add_key(1., brighten(Color::rgba(1., 0.3, 0., 0.), brightness));
+
+    let spawner = Spawner::once((60.0 * scale).into(), false);
+
+    EffectAsset {
+        name: "Target".into(),
+        capacity: 32768,
+        spawner,
+        ..default()
+    }
+    .init(PositionSphereModifier {
+        radius: 10.,
+        speed: 250.0.into(),
+        dimension: ShapeDimension::Volume,
+        ..default()
+    })
+    .init(ParticleLifetimeModifier { lifetime: 0.5 })
+    .render(SizeOverLifetimeModifier {
+        gradient: Gradient::constant(Vec2::splat(4.)),
+    })
+    .render(ColorOverLifetimeModifier { gradient })
+}
+
+fn shimmer_effect(scale: f32, brightness: f32) -> EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0., brighten(Color::rgba(0.8, 0.5, 1., 0.8), brightness));
+    gradient.add_key(1., brighten(Color::rgba(0.3, 0., 1., 0.), brightness));
+
+    let spawner = Spawner::rate((120.0 * scale).into()).with_active(false);
+
+    EffectAsset {
+        name: "Shimmer".into(),
+        capacity: 4096,
+        spawner,
+        ..default()
+    }
+    .init(PositionSphereModifier {
+        radius: PLAYER_RADIUS,
+        speed: 20.0.into(),
+        dimension: ShapeDimension::Volume,
+        ..default()
+    })
+    .init(ParticleLifetimeModifier { lifetime: 0.2 })
+    .render(SizeOverLifetimeModifier {
+        gradient: Gradient::constant(Vec2::splat(3.)),
+    })
+    .render(ColorOverLifetimeModifier { gradient })
+}
+
+fn steam_effect(scale: f32, brightness: f32) -> EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0., brighten(Color::rgba(1., 1., 1., 0.6), brightness));
+    gradient.add_key(1., brighten(Color::rgba(0.7, 0.7, 0.7, 0.), brightness));
+
+    let spawner = Spawner::rate((150.0 * scale).into()).with_active(false);
+
+    EffectAsset {
+        name: "Steam".into(),
+        capacity: 4096,
+        spawner,
+        ..default()
+    }
+    .init(PositionSphereModifier {
+        radius: PLAYER_RADIUS,
+        speed: 60.0.into(),
+        dimension: ShapeDimension::Surface,
+        ..default()
+    })
+    .init(ParticleLifetimeModifier { lifetime: 0.6 })
+    .render(SizeOverLifetimeModifier {
+        gradient: Gradient::constant(Vec2::splat(6.)),
+    })
+    .render(ColorOverLifetimeModifier { gradient })
+}
+
+fn scrape_effect(scale: f32, brightness: f32) -> EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0., brighten(Color::rgba(1., 0.9, 0.5, 1.), brightness));
+    gradient.add_key(1., brighten(Color::rgba(1., 0.4, 0., 0.), brightness));
+
+    let spawner = Spawner::rate((90.0 * scale).into()).with_active(false);
+
+    EffectAsset {
+        name: "Scrape".into(),
+        capacity: 4096,
+        spawner,
+        ..default()
+    }
+    .init(PositionSphereModifier {
+        radius: 2.,
+        speed: 120.0.into(),
+        dimension: ShapeDimension::Surface,
+        ..default()
+    })
+    .init(ParticleLifetimeModifier { lifetime: 0.25 })
+    .render(SizeOverLifetimeModifier {
+        gradient: Gradient::constant(Vec2::splat(2.)),
+    })
+    .render(ColorOverLifetimeModifier { gradient })
+}
+
+fn drift_effect(scale: f32, brightness: f32) -> EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0., brighten(Color::rgba(1., 0.9, 0.3, 1.), brightness));
+    gradient.add_key(1., brighten(Color::rgba(1., 0.4, 0., 0.), brightness));
+
+    let spawner = Spawner::once((40.0 * scale).into(), false);
+
+    EffectAsset {
+        name: "Drift".into(),
+        capacity: 32768,
+        spawner,
+        ..default()
+    }
+    .init(PositionSphereModifier {
+        radius: PLAYER_RADIUS * 0.5,
+        speed: 150.0.into(),
+        dimension: ShapeDimension::Surface,
+        ..default()
+    })
+    .init(ParticleLifetimeModifier { lifetime: 0.4 })
+    .render(SizeOverLifetimeModifier {
+        gradient: Gradient::constant(Vec2::splat(3.)),
+    })
+    .render(ColorOverLifetimeModifier { gradient })
+}
+
+/// Particles falling into the tile from its edge, continuously.
+fn gravity_flip_effect(scale: f32, brightness: f32) -> EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0., brighten(Color::rgba(0.7, 0.3, 1., 0.), brightness));
+    gradient.add_key(0.3, brighten(Color::rgba(0.7, 0.3, 1., 0.8), brightness));
+    gradient.add_key(1., brighten(Color::rgba(0.2, 0., 0.6, 0.), brightness));
+
+    let spawner = Spawner::rate((30.0 * scale).into());
+
+    EffectAsset {
+        name: "Gravity flip".into(),
+        capacity: 4096,
+        spawner,
+        ..default()
+    }
+    .init(PositionSphereModifier {
+        radius: 40.,
+        speed: (-40.0).into(),
+        dimension: ShapeDimension::Surface,
+        ..default()
+    })
+    .init(ParticleLifetimeModifier { lifetime: 1. })
+    .render(SizeOverLifetimeModifier {
+        gradient: Gradient::constant(Vec2::splat(3.)),
+    })
+    .render(ColorOverLifetimeModifier { gradient })
+}
+
+/// Burst on new contacts of the player, ignoring the ones that are sustained.
+#[allow(clippy::too_many_arguments)] // The tint follows the combo and the contacts
 fn trigger_collision_effects(
-    mut collision_events: EventReader<CollisionEvent>,
-    mut effect: Query<
-        (&mut ParticleEffect, &mut Transform),
-        (With<CollisionEffect>, Without<Player>),
+    config: Res<ParticleConfig>,
+    constants: Res<Constants>,
+    contacts: Res<PlayerContacts>,
+    combo: Res<BounceCombo>,
+    mut cursors: ResMut<PoolCursors>,
+    mut effects: ResMut<Assets<EffectAsset>>,
+    mut pool: Query<
+        (
+            &Pooled,
+            &mut ParticleEffect,
+            &mut Transform,
+            &EffectLods,
+            &mut CollisionTint,
+        ),
+        Without<Player>,
     >,
     player: Query<&Transform, With<Player>>,
+    walls: Query<&WallMaterial>,
 ) {
-    for collision_event in collision_events.iter() {
-        if let CollisionEvent::Started(..) = collision_event {
-            let (mut effect, mut effect_transform) = effect.single_mut();
-            let transform = player.single();
-            effect_transform.translation = transform.translation;
-            effect.maybe_spawner().unwrap().reset();
+    let player_transform = match player.get_single() {
+        Ok(transform) => transform,
+        Err(_) => return,
+    };
+    // From the color of the surface hit up to white at the max combo
+    let brightness = (f32::from(combo.count) / MAX_BOUNCE_COMBO).min(1.);
+
+    for surface in &contacts.started {
+        let index = match cursors.next(EffectPool::Collision, &config) {
+            Some(index) => index,
+            None => return,
+        };
+        let (_, mut effect, mut effect_transform, lods, mut built_tint) = match pool
+            .iter_mut()
+            .find(|(pooled, ..)| pooled.pool == EffectPool::Collision && pooled.index == index)
+        {
+            Some(pooled) => pooled,
+            None => continue,
+        };
+
+        let color = walls.get(*surface).map_or(Color::GRAY, |wall| wall.color);
+        let tint = CollisionTint {
+            color: color * (1. - brightness) + Color::WHITE * brightness,
+            brightness: particle_brightness(&constants),
+        };
+        // Hitting the same wall at the same combo reuses the assets
+        if *built_tint != tint {
+            for lod in [EffectLod::Full, EffectLod::Low] {
+                if let Some(asset) = effects.get_mut(lods.handle(lod)) {
+                    *asset = collision_effect(
+                        config.quality.scale() * lod.scale(),
+                        tint.brightness,
+                        tint.color,
+                    );
+                }
+            }
+            *built_tint = tint;
         }
+
+        trigger_effect_at(
+            &mut effect,
+            &mut effect_transform,
+            player_transform.translation,
+        );
     }
 }
 
+#[allow(clippy::too_many_arguments)] // The brake effect is kept out of the pool
 fn trigger_input_effects(
-    // mut impulse_cooldown: Local<ImpulseCooldown>,
+    config: Res<ParticleConfig>,
+    constants: Res<Constants>,
+    mut cursors: ResMut<PoolCursors>,
+    mut effects: ResMut<Assets<EffectAsset>>,
     mut input_events: EventReader<InputEvent>,
-    mut explosion_effect: Query<
+    mut pool: Query<(&Pooled, &mut ParticleEffect, &mut Transform, &EffectLods), Without<Player>>,
+    mut brake_effect: Query<
         (&mut ParticleEffect, &mut Transform),
-        (
-            With<ExplosionEffect>,
-            Without<Player>,
-            Without<PropulsorEffect>,
-        ),
+        (With<BrakeEffect>, Without<Player>, Without<Pooled>),
     >,
-    mut propulsor_effect: Query<
+    player: Query<&Transform, With<Player>>,
+) {
+    let player_translation = match player.get_single() {
+        Ok(transform) => transform.translation,
+        Err(_) => return,
+    };
+
+    for input_event in input_events.iter() {
+        // Bursts behind the player when pushed in a direction, on it otherwise
+        let (offset, blast_radius, push) = match *input_event {
+            InputEvent::Impulse { direction, charge } => {
+                let radius = EXPLOSION_RADIUS * charge.mul_add(EXPLOSION_CHARGE_GROWTH, 1.);
+                let push = if config.directional_explosion {
+                    -direction.normalize_or_zero()
+                } else {
+                    Vec2::ZERO
+                };
+                (
+                    direction * -PLAYER_RADIUS,
+                    radius.min(config.max_explosion_radius),
+                    push,
+                )
+            }
+            InputEvent::Accelerate | InputEvent::BoostForward => {
+                (Vec2::ZERO, EXPLOSION_RADIUS, Vec2::ZERO)
+            }
+            InputEvent::EmergencyStop => {
+                if let Ok((mut effect, mut effect_transform)) = brake_effect.get_single_mut() {
+                    trigger_effect_at(&mut effect, &mut effect_transform, player_translation);
+                }
+                continue;
+            }
+            InputEvent::Stabilisation
+            | InputEvent::StabilisationReleased
+            | InputEvent::PhaseShift
+            | InputEvent::Vent => continue,
+        };
+
+        let index = match cursors.next(EffectPool::Explosion, &config) {
+            Some(index) => index,
+            None => continue,
+        };
+        let (_, mut effect, mut effect_transform, lods) = match pool
+            .iter_mut()
+            .find(|(pooled, ..)| pooled.pool == EffectPool::Explosion && pooled.index == index)
+        {
+            Some(pooled) => pooled,
+            None => continue,
+        };
+
+        resize_explosion(
+            &mut effect,
+            &mut effects,
+            lods,
+            config.quality.scale(),
+            particle_brightness(&constants),
+            blast_radius,
+            push,
+        );
+        trigger_effect_at(
+            &mut effect,
+            &mut effect_transform,
+            player_translation + offset.extend(0.),
+        );
+    }
+}
+
+struct PropulsorInterval(Cooldown);
+
+impl Default for PropulsorInterval {
+    fn default() -> Self {
+        Self(Cooldown::from_seconds(PROPULSOR_BURST_SECS))
+    }
+}
+
+/// Burst behind the player at a steady rate while the force is held, whatever the frame rate.
+fn trigger_propulsor_effect(
+    config: Res<ParticleConfig>,
+    time: Res<Time>,
+    held_force: Res<HeldForce>,
+    mut cursors: ResMut<PoolCursors>,
+    mut interval: Local<PropulsorInterval>,
+    mut pool: Query<(&Pooled, &mut ParticleEffect, &mut Transform), Without<Player>>,
+    player: Query<&Transform, With<Player>>,
+) {
+    interval.0.tick(time.delta());
+    if held_force.0 == Vec2::ZERO || !interval.0.finished() {
+        return;
+    }
+    let player_translation = match player.get_single() {
+        Ok(transform) => transform.translation,
+        Err(_) => return,
+    };
+    let index = match cursors.next(EffectPool::Propulsor, &config) {
+        Some(index) => index,
+        None => return,
+    };
+    interval.0.start();
+
+    let pooled = pool
+        .iter_mut()
+        .find(|(pooled, ..)| pooled.pool == EffectPool::Propulsor && pooled.index == index);
+    if let Some((_, mut effect, mut effect_transform)) = pooled {
+        let offset = held_force.0.normalize_or_zero() * -PLAYER_RADIUS;
+        trigger_effect_at(
+            &mut effect,
+            &mut effect_transform,
+            player_translation + offset.extend(0.),
+        );
+    }
+}
+
+/// Rebuild the assets of the explosion for `radius`.
+///
+/// The effect gets a copy of the new spawner too, as the one it holds overrides the spawner of the asset.
+fn resize_explosion(
+    effect: &mut ParticleEffect,
+    effects: &mut Assets<EffectAsset>,
+    lods: &EffectLods,
+    scale: f32,
+    brightness: f32,
+    radius: f32,
+    push: Vec2,
+) {
+    for lod in [EffectLod::Full, EffectLod::Low] {
+        if let Some(asset) = effects.get_mut(lods.handle(lod)) {
+            *asset = explosion_effect(scale * lod.scale(), brightness, radius, push);
+        }
+    }
+    let handle = lods.handle(lods.current).clone();
+    if let Some(asset) = effects.get(&handle) {
+        *effect = ParticleEffect::new(handle).with_spawner(asset.spawner);
+    }
+}
+
+/// Make the player sparkle while it is kept cool.
+fn update_sparkle_effect(
+    mut effect: Query<
         (&mut ParticleEffect, &mut Transform),
-        (
-            With<PropulsorEffect>,
-            Without<Player>,
-            Without<ExplosionEffect>,
-        ),
+        (With<SparkleEffect>, Without<Player>),
+    >,
+    player: Query<(&Transform, &Heat), With<Player>>,
+) {
+    let ((mut effect, mut effect_transform), (transform, heat)) =
+        match (effect.get_single_mut(), player.get_single()) {
+            (Ok(effect), Ok(player)) => (effect, player),
+            _ => return,
+        };
+
+    effect_transform.translation = transform.translation;
+
+    if let Some(spawner) = effect.maybe_spawner() {
+        spawner.set_active(heat.amount < SPARKLE_HEAT_THRESHOLD);
+    }
+}
+
+/// Make the player shimmer while it passes through the walls.
+fn update_shimmer_effect(
+    phase_shift: Res<PhaseShift>,
+    mut effect: Query<
+        (&mut ParticleEffect, &mut Transform),
+        (With<ShimmerEffect>, Without<Player>),
     >,
     player: Query<&Transform, With<Player>>,
 ) {
-    // impulse_cooldown.0.tick(time.delta());
+    let ((mut effect, mut effect_transform), transform) =
+        match (effect.get_single_mut(), player.get_single()) {
+            (Ok(effect), Ok(player)) => (effect, player),
+            _ => return,
+        };
 
-    for input_event in input_events.iter() {
-        match input_event {
-            InputEvent::Impulse { direction } => {
-                let (mut effect, mut effect_transform) = explosion_effect.single_mut();
-                let transform = player.single();
+    effect_transform.translation = transform.translation;
+
+    if let Some(spawner) = effect.maybe_spawner() {
+        spawner.set_active(phase_shift.active);
+    }
+}
 
-                let player_body = Vec3::from((*direction * -PLAYER_RADIUS, 0.));
-                effect_transform.translation = transform.translation + player_body;
+/// Let steam out of the player while it vents.
+fn update_steam_effect(
+    venting: Res<Venting>,
+    mut effect: Query<(&mut ParticleEffect, &mut Transform), (With<SteamEffect>, Without<Player>)>,
+    player: Query<&Transform, With<Player>>,
+) {
+    let ((mut effect, mut effect_transform), transform) =
+        match (effect.get_single_mut(), player.get_single()) {
+            (Ok(effect), Ok(player)) => (effect, player),
+            _ => return,
+        };
 
-                effect.maybe_spawner().unwrap().reset();
-            }
-            InputEvent::Stabilisation => {}
-            InputEvent::Accelerate => {
-                let (mut effect, mut effect_transform) = explosion_effect.single_mut();
-                effect_transform.translation = player.single().translation;
+    effect_transform.translation = transform.translation;
 
-                effect.maybe_spawner().unwrap().reset();
-            }
-            InputEvent::Force { direction } => {
-                let (mut effect, mut effect_transform) = propulsor_effect.single_mut();
-                let transform = player.single();
+    if let Some(spawner) = effect.maybe_spawner() {
+        spawner.set_active(venting.active);
+    }
+}
 
-                let player_body = Vec3::from((*direction * -PLAYER_RADIUS, 0.));
-                effect_transform.translation = transform.translation + player_body;
-                effect.maybe_spawner().unwrap().reset();
-            }
+struct ScrapeDelay(Cooldown);
+
+impl Default for ScrapeDelay {
+    fn default() -> Self {
+        Self(Cooldown::from_seconds(SCRAPE_DELAY_SECS))
+    }
+}
+
+/// Throw sparks where the player slides along a wall, until it leaves the wall or slows down.
+fn update_scrape_effect(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    mut delay: Local<ScrapeDelay>,
+    mut effect: Query<(&mut ParticleEffect, &mut Transform), (With<ScrapeEffect>, Without<Player>)>,
+    player: Query<(Entity, &Transform, &Velocity, &Touching), With<Player>>,
+) {
+    let ((mut effect, mut effect_transform), (entity, transform, velocity, touching)) =
+        match (effect.get_single_mut(), player.get_single()) {
+            (Ok(effect), Ok(player)) => (effect, player),
+            _ => return,
+        };
+
+    delay.0.tick(time.delta());
+    let normal = wall_push_off(&rapier_context, entity, touching);
+    if normal == Vec2::ZERO {
+        delay.0.start();
+    }
+    let along_wall = velocity.linvel - normal * velocity.linvel.dot(normal);
+    let scraping = normal != Vec2::ZERO && delay.0.finished() && along_wall.length() > SCRAPE_SPEED;
+
+    if scraping {
+        // On the side of the player touching the wall
+        effect_transform.translation = transform.translation - (normal * PLAYER_RADIUS).extend(0.);
+    }
+    if let Some(spawner) = effect.maybe_spawner() {
+        spawner.set_active(scraping);
+    }
+}
+
+/// Burst behind the player when a sharp turn is boosted.
+fn trigger_drift_effect(
+    mut drift_events: EventReader<DriftBoost>,
+    mut effect: Query<(&mut ParticleEffect, &mut Transform), (With<DriftEffect>, Without<Player>)>,
+    player: Query<&Transform, With<Player>>,
+) {
+    let ((mut effect, mut effect_transform), transform) =
+        match (effect.get_single_mut(), player.get_single()) {
+            (Ok(effect), Ok(player)) => (effect, player),
+            _ => return,
+        };
+    for drift in drift_events.iter() {
+        trigger_effect_at(
+            &mut effect,
+            &mut effect_transform,
+            transform.translation + (drift.direction * -PLAYER_RADIUS).extend(0.),
+        );
+    }
+}
+
+fn attach_gravity_flip_effects(
+    mut commands: Commands,
+    mut effects: ResMut<Assets<EffectAsset>>,
+    config: Res<ParticleConfig>,
+    constants: Res<Constants>,
+    tiles: Query<Entity, Added<GravityFlip>>,
+) {
+    let scale = config.quality.scale();
+    let brightness = particle_brightness(&constants);
+
+    for tile in &tiles {
+        let effect = spawn_particle_effect(
+            &mut commands,
+            &mut effects,
+            "Gravity flip effect",
+            GravityFlipEffect,
+            |lod_scale| gravity_flip_effect(scale * lod_scale, brightness),
+        );
+        commands.entity(tile).add_child(effect);
+    }
+}
+
+/// Flash around the player when it parries a hit.
+fn trigger_parry_effect(
+    mut parried_events: EventReader<Parried>,
+    mut effect: Query<(&mut ParticleEffect, &mut Transform), With<ParryEffect>>,
+) {
+    let (mut effect, mut effect_transform) = match effect.get_single_mut() {
+        Ok(effect) => effect,
+        Err(_) => return,
+    };
+    for parried in parried_events.iter() {
+        trigger_effect_at(
+            &mut effect,
+            &mut effect_transform,
+            parried.position.extend(0.),
+        );
+    }
+}
+
+/// Burst where a target breaks.
+fn trigger_target_effect(
+    mut broken_events: EventReader<TargetBroken>,
+    mut effect: Query<(&mut ParticleEffect, &mut Transform), With<TargetEffect>>,
+) {
+    let (mut effect, mut effect_transform) = match effect.get_single_mut() {
+        Ok(effect) => effect,
+        Err(_) => return,
+    };
+    for broken in broken_events.iter() {
+        trigger_effect_at(
+            &mut effect,
+            &mut effect_transform,
+            broken.position.extend(0.),
+        );
+    }
+}
+
+/// Swap the effects that moved to the level of detail matching their place on screen.
+fn select_effect_lods(
+    effects: Res<Assets<EffectAsset>>,
+    camera: Query<(&GlobalTransform, &OrthographicProjection), With<Camera>>,
+    mut particle_effects: Query<
+        (&mut ParticleEffect, &mut EffectLods, &Transform),
+        Changed<Transform>,
+    >,
+) {
+    let (camera_transform, projection) = match camera.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let view_center = camera_transform.translation().truncate();
+
+    for (mut effect, mut lods, transform) in &mut particle_effects {
+        let screen_distance =
+            transform.translation.truncate().distance(view_center) / projection.scale;
+        let lod = if projection.scale >= LOW_DETAIL_ZOOM || screen_distance > LOW_DETAIL_DISTANCE {
+            EffectLod::Low
+        } else {
+            EffectLod::Full
+        };
+        if lod == lods.current {
+            continue;
+        }
+
+        let handle = lods.handle(lod).clone();
+        let spawner = match effects.get(&handle) {
+            Some(asset) => asset.spawner,
+            None => continue,
+        };
+        lods.current = lod;
+
+        // The previous spawner was just triggered, or toggled for the continuous effects
+        let active = effect
+            .maybe_spawner()
+            .map_or(true, |spawner| spawner.is_active());
+        *effect = ParticleEffect::new(handle).with_spawner(spawner);
+        if let Some(spawner) = effect.maybe_spawner() {
+            spawner.set_active(active);
+            spawner.reset();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::AssetPlugin, ecs::event::Events, prelude::*};
+    use bevy_hanabi::{EffectAsset, ParticleEffect, Spawner};
+    use bevy_rapier2d::prelude::*;
+
+    use super::{
+        trigger_input_effects, EffectLod, EffectLods, EffectPool, ParticleConfig, PoolCursors,
+        Pooled,
+    };
+    use crate::{
+        apply_forces,
+        contacts::Touching,
+        difficulty::Difficulty,
+        inputs::{HeldForce, InputDenied, InputEvent, InputSettings},
+        targets::Targets,
+        vent::Venting,
+        Constants, DriftBoost, Heat, ImpulseCooldown, Player, Stabilising, PLAYER_RADIUS,
+    };
+
+    #[test]
+    fn test_impulse_moves_player_and_triggers_explosion() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin)
+            .add_asset::<EffectAsset>()
+            .add_event::<InputEvent>()
+            .add_event::<InputDenied>()
+            .add_event::<DriftBoost>()
+            .init_resource::<ParticleConfig>()
+            .init_resource::<PoolCursors>()
+            .init_resource::<HeldForce>()
+            .init_resource::<Constants>()
+            .init_resource::<Difficulty>()
+            .init_resource::<InputSettings>()
+            .init_resource::<Stabilising>()
+            .init_resource::<ImpulseCooldown>()
+            .init_resource::<Venting>()
+            .init_resource::<RapierContext>()
+            .init_resource::<Targets>()
+            .add_system(apply_forces)
+            .add_system(trigger_input_effects);
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Player)
+            .insert(Transform::from_xyz(100., 50., 0.))
+            .insert(Touching::default())
+            .insert_bundle((
+                Velocity::default(),
+                ExternalImpulse::default(),
+                ExternalForce::default(),
+                Damping::default(),
+                Heat::default(),
+                ReadMassProperties(MassProperties {
+                    mass: 2.,
+                    ..MassProperties::default()
+                }),
+            ))
+            .id();
+        let explosion = app
+            .world
+            .spawn()
+            .insert(Pooled {
+                pool: EffectPool::Explosion,
+                index: 0,
+            })
+            .insert(EffectLods {
+                full: Handle::default(),
+                low: Handle::default(),
+                current: EffectLod::Full,
+            })
+            .insert(Transform::default())
+            .insert(
+                ParticleEffect::new(Handle::default())
+                    .with_spawner(Spawner::once(1.0.into(), false)),
+            )
+            .id();
+
+        app.world
+            .resource_mut::<Events<InputEvent>>()
+            .send(InputEvent::Impulse {
+                direction: Vec2::X,
+                charge: 0.,
+            });
+        app.update();
+
+        let impulse = app
+            .world
+            .get::<ExternalImpulse>(player)
+            .map(|ext| ext.impulse);
+        assert!(impulse.map_or(false, |impulse| impulse.x > 0.));
+
+        let burst = app.world.get::<Transform>(explosion).map(|t| t.translation);
+        assert_eq!(burst, Some(Vec3::new(100. - PLAYER_RADIUS, 50., 0.)));
+    }
+}
@@ -0,0 +1,107 @@
+//! Phase shift: the player briefly passes through the walls.
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    cooldown::Cooldown,
+    inputs::{translate_actions, InputEvent},
+    menu::gameplay_running,
+    Player, WallMaterial,
+};
+
+/// Collision group of the walls, which the player stops colliding with while phasing.
+const WALL_GROUP: u32 = 1 << 1;
+
+const PHASE_SHIFT_SECS: f32 = 0.6;
+
+pub struct PhasePlugin;
+
+impl Plugin for PhasePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PhaseShift>()
+            .add_system(
+                start_phase_shift
+                    .with_run_criteria(gameplay_running)
+                    .after(translate_actions),
+            )
+            .add_system(
+                end_phase_shift
+                    .with_run_criteria(gameplay_running)
+                    .after(start_phase_shift),
+            );
+    }
+}
+
+/// Out of phase with the walls, until the duration is finished and the player is clear of them.
+pub struct PhaseShift {
+    pub active: bool,
+    duration: Cooldown,
+}
+
+impl Default for PhaseShift {
+    fn default() -> Self {
+        Self {
+            active: false,
+            duration: Cooldown::from_seconds(PHASE_SHIFT_SECS),
+        }
+    }
+}
+
+/// Collision groups of the walls.
+pub fn wall_groups() -> CollisionGroups {
+    CollisionGroups::new(WALL_GROUP, u32::MAX)
+}
+
+/// Collision groups of the player going through the walls, still colliding with the other bodies.
+fn phasing_groups() -> CollisionGroups {
+    CollisionGroups::new(u32::MAX, !WALL_GROUP)
+}
+
+fn start_phase_shift(
+    mut input_events: EventReader<InputEvent>,
+    mut phase_shift: ResMut<PhaseShift>,
+    mut player: Query<&mut CollisionGroups, With<Player>>,
+) {
+    for input_event in input_events.iter() {
+        if !matches!(input_event, InputEvent::PhaseShift) || phase_shift.active {
+            continue;
+        }
+        phase_shift.active = true;
+        phase_shift.duration.start();
+
+        for mut groups in &mut player {
+            *groups = phasing_groups();
+        }
+    }
+}
+
+/// Restore the collisions with the walls once the duration is over,
+/// waiting for the player to leave the walls it is inside of.
+fn end_phase_shift(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    mut phase_shift: ResMut<PhaseShift>,
+    mut player: Query<(&mut CollisionGroups, &Transform, &Collider), With<Player>>,
+    walls: Query<(), With<WallMaterial>>,
+) {
+    phase_shift.duration.tick(time.delta());
+    if !phase_shift.active || !phase_shift.duration.finished() {
+        return;
+    }
+
+    for (mut groups, transform, collider) in &mut player {
+        let is_wall = |entity| walls.contains(entity);
+        let overlapping_wall = rapier_context.intersection_with_shape(
+            transform.translation.truncate(),
+            transform.rotation.to_euler(EulerRot::ZYX).0,
+            collider,
+            QueryFilter::new().predicate(&is_wall),
+        );
+        if overlapping_wall.is_some() {
+            return;
+        }
+        *groups = CollisionGroups::default();
+    }
+    phase_shift.active = false;
+}
@@ -0,0 +1,113 @@
+//! Detect when the player has stopped, for the features resetting on it.
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{cooldown::Cooldown, menu::gameplay_running, Constants, Player};
+
+pub struct StopPlugin;
+
+impl Plugin for StopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerStop>()
+            // Along the contacts, so that their consumers already know about it
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                detect_stop.with_run_criteria(gameplay_running),
+            );
+    }
+}
+
+/// Time the player stayed slower than `Constants::stop_speed`.
+pub struct PlayerStop {
+    /// Restarted while moving, the player is stopped once it is finished.
+    delay: Cooldown,
+}
+
+impl Default for PlayerStop {
+    fn default() -> Self {
+        Self {
+            // The duration is kept in sync with `Constants::stop_duration` by `detect_stop`
+            delay: Cooldown::from_seconds(0.),
+        }
+    }
+}
+
+impl PlayerStop {
+    /// The player stayed slower than `Constants::stop_speed` for `Constants::stop_duration`.
+    pub fn is_stopped(&self) -> bool {
+        self.delay.finished()
+    }
+}
+
+pub fn detect_stop(
+    constants: Res<Constants>,
+    time: Res<Time>,
+    mut stop: ResMut<PlayerStop>,
+    player: Query<&Velocity, With<Player>>,
+) {
+    let speed = player
+        .get_single()
+        .map_or(0., |velocity| velocity.linvel.length());
+
+    stop.delay.set_duration(constants.stop_duration);
+    if speed >= constants.stop_speed {
+        stop.delay.start();
+    } else {
+        stop.delay.tick(time.delta());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::prelude::*;
+    use bevy_rapier2d::prelude::*;
+
+    use super::{detect_stop, PlayerStop};
+    use crate::{Constants, Player};
+
+    #[test]
+    fn test_detect_stop() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<Constants>()
+            .init_resource::<PlayerStop>()
+            .add_system(detect_stop);
+        let player = app
+            .world
+            .spawn()
+            .insert(Player)
+            .insert(Velocity::linear(Vec2::X * 1000.))
+            .id();
+
+        let mut now = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        let mut step = |app: &mut App, secs: f32| {
+            now += Duration::from_secs_f32(secs);
+            app.world.resource_mut::<Time>().update_with_instant(now);
+            app.update();
+            app.world.resource::<PlayerStop>().is_stopped()
+        };
+
+        assert!(!step(&mut app, 0.1));
+
+        // Slowing down, but not for long enough yet
+        let stop_duration = app.world.resource::<Constants>().stop_duration;
+        app.world
+            .get_mut::<Velocity>(player)
+            .expect("Player velocity")
+            .linvel = Vec2::ZERO;
+        assert!(!step(&mut app, 0.));
+        assert!(!step(&mut app, stop_duration * 0.5));
+        assert!(step(&mut app, stop_duration));
+
+        // Moving again
+        app.world
+            .get_mut::<Velocity>(player)
+            .expect("Player velocity")
+            .linvel = Vec2::Y * 1000.;
+        assert!(!step(&mut app, 0.1));
+    }
+}
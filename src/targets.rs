@@ -0,0 +1,254 @@
+//! Target practice: waves of targets along the edges of the arena, broken by launching into them.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_inspector_egui::{
+    bevy_egui::{egui, EguiContext},
+    Inspectable, InspectorPlugin,
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::{contacts::GameplayCollision, menu::gameplay_running, Arena, Player, Z};
+
+const TARGET_RADIUS: f32 = 20.;
+/// Distance between the targets and the edges of the arena.
+const TARGET_EDGE_MARGIN: f32 = 50.;
+/// Hits taking a target down.
+const TARGET_HITS: u8 = 2;
+/// Impact speed from which the player hits a target, so that it has to be launched into it.
+const TARGET_HIT_SPEED: f32 = 300.;
+/// Score of each broken target.
+const TARGET_POINTS: u32 = 100;
+/// Score of each impulse aimed right at a target.
+const SKILL_SHOT_POINTS: u32 = 20;
+const TARGET_COLOR: Color = Color::GOLD;
+const DAMAGED_TARGET_COLOR: Color = Color::ORANGE_RED;
+/// Targets of a wave, in the corners and the middle of the top and bottom edges.
+const WAVE_LAYOUT: [Vec2; 6] = [
+    Vec2::new(-1., 1.),
+    Vec2::new(0., 1.),
+    Vec2::new(1., 1.),
+    Vec2::new(1., -1.),
+    Vec2::new(0., -1.),
+    Vec2::new(-1., -1.),
+];
+
+pub struct TargetPracticePlugin;
+
+impl Plugin for TargetPracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InspectorPlugin::<TargetPracticeSettings>::new())
+            .add_event::<TargetBroken>()
+            .init_resource::<Targets>()
+            .add_startup_system(setup_targets)
+            .add_system(hit_targets.with_run_criteria(gameplay_running))
+            .add_system(show_target_practice.after(hit_targets));
+    }
+}
+
+/// Read at startup, when the first wave is placed.
+#[derive(Inspectable, Default)]
+pub struct TargetPracticeSettings {
+    enabled: bool,
+}
+
+/// Progress through the waves of targets.
+#[derive(Default)]
+pub struct Targets {
+    wave: u16,
+    /// Targets of the current wave still standing.
+    remaining: usize,
+    score: u32,
+    /// Time spent on the current wave, not counting the pauses.
+    wave_time: f64,
+    /// Time taken to clear the last wave, in seconds.
+    last_clear: Option<f64>,
+}
+
+/// Sensor broken after `TARGET_HITS` hits of the player.
+#[derive(Component)]
+pub struct Target {
+    hits_left: u8,
+}
+
+/// Standing targets the impulses of the player are aimed at.
+#[derive(SystemParam)]
+pub struct SkillShots<'w, 's> {
+    targets: ResMut<'w, Targets>,
+    standing: Query<'w, 's, &'static Transform, With<Target>>,
+    players: Query<'w, 's, &'static Transform, With<Player>>,
+}
+
+impl SkillShots<'_, '_> {
+    /// Score an impulse aimed within `max_angle` radians of a target, returning whether it was.
+    pub fn reward(&mut self, direction: Vec2, max_angle: f32) -> bool {
+        let origin = match self.players.get_single() {
+            Ok(transform) => transform.translation.truncate(),
+            Err(_) => return false,
+        };
+        let positions = self
+            .standing
+            .iter()
+            .map(|transform| transform.translation.truncate());
+        let skill_shot =
+            aim_error(origin, direction, positions).map_or(false, |error| error <= max_angle);
+        if skill_shot {
+            self.targets.score += SKILL_SHOT_POINTS;
+        }
+        skill_shot
+    }
+}
+
+/// Smallest angle between the `direction` aimed from `origin` and the `targets`, in radians.
+fn aim_error(origin: Vec2, direction: Vec2, targets: impl Iterator<Item = Vec2>) -> Option<f32> {
+    if direction == Vec2::ZERO {
+        return None;
+    }
+    targets
+        .filter(|&target| target != origin)
+        .map(|target| direction.angle_between(target - origin).abs())
+        .reduce(f32::min)
+}
+
+/// A target broke at the position.
+pub struct TargetBroken {
+    pub position: Vec2,
+}
+
+fn setup_targets(
+    mut commands: Commands,
+    settings: Res<TargetPracticeSettings>,
+    arena: Res<Arena>,
+    mut targets: ResMut<Targets>,
+) {
+    if settings.enabled {
+        spawn_wave(&mut commands, &arena, &mut targets);
+    }
+}
+
+fn spawn_wave(commands: &mut Commands, arena: &Arena, targets: &mut Targets) {
+    let extent = arena.half_size - TARGET_EDGE_MARGIN;
+
+    for place in WAVE_LAYOUT {
+        let position = place * extent;
+        commands
+            .spawn()
+            .insert(Name::new("Target"))
+            .insert(Target {
+                hits_left: TARGET_HITS,
+            })
+            .insert_bundle((
+                Collider::ball(TARGET_RADIUS),
+                Sensor,
+                ColliderDebugColor(TARGET_COLOR),
+            ))
+            .insert_bundle(TransformBundle::from(Transform::from_xyz(
+                position.x, position.y, Z,
+            )));
+    }
+    targets.wave += 1;
+    targets.remaining = WAVE_LAYOUT.len();
+    targets.wave_time = 0.;
+}
+
+/// Damage the targets the player launches into, placing a new wave once they are all broken.
+#[allow(clippy::too_many_arguments)] // Breaking a target can place the next wave
+fn hit_targets(
+    mut commands: Commands,
+    time: Res<Time>,
+    arena: Res<Arena>,
+    mut targets: ResMut<Targets>,
+    mut collisions: EventReader<GameplayCollision>,
+    mut broken_events: EventWriter<TargetBroken>,
+    players: Query<(), With<Player>>,
+    mut standing: Query<(&mut Target, &mut ColliderDebugColor, &Transform)>,
+) {
+    targets.wave_time += time.delta_seconds_f64();
+
+    for collision in collisions.iter() {
+        let [a, b] = collision.entities;
+        if !collision.started
+            || collision.impact_speed < TARGET_HIT_SPEED
+            || !(players.contains(a) || players.contains(b))
+        {
+            continue;
+        }
+        let entity = if standing.contains(a) { a } else { b };
+        let (mut target, mut debug_color, transform) = match standing.get_mut(entity) {
+            Ok(target) => target,
+            Err(_) => continue,
+        };
+        // Already broken by a previous collision of the frame
+        if target.hits_left == 0 {
+            continue;
+        }
+        target.hits_left -= 1;
+        debug_color.0 = DAMAGED_TARGET_COLOR;
+        if target.hits_left > 0 {
+            continue;
+        }
+
+        commands.entity(entity).despawn_recursive();
+        broken_events.send(TargetBroken {
+            position: transform.translation.truncate(),
+        });
+        targets.score += TARGET_POINTS;
+        targets.remaining = targets.remaining.saturating_sub(1);
+
+        if targets.remaining == 0 {
+            targets.last_clear = Some(targets.wave_time);
+            spawn_wave(&mut commands, &arena, &mut targets);
+        }
+    }
+}
+
+fn show_target_practice(
+    mut egui_context: ResMut<EguiContext>,
+    settings: Res<TargetPracticeSettings>,
+    targets: Res<Targets>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    egui::Window::new("Target practice").show(egui_context.ctx_mut(), |ui| {
+        ui.label(format!("Wave {}", targets.wave));
+        ui.label(format!(
+            "Targets {}/{}",
+            targets.remaining,
+            WAVE_LAYOUT.len()
+        ));
+        ui.label(format!("Time {:.2}s", targets.wave_time));
+        if let Some(last_clear) = targets.last_clear {
+            ui.label(format!("Last {last_clear:.2}s"));
+        }
+        ui.label(format!("Score {}", targets.score));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Vec2;
+
+    use super::aim_error;
+
+    #[test]
+    fn test_aim_error() {
+        let targets = [Vec2::new(100., 0.), Vec2::new(0., 100.)];
+        let error = |direction| aim_error(Vec2::ZERO, direction, targets.into_iter());
+
+        // The best-aligned target counts
+        let aligned = error(Vec2::X).expect("Aim error");
+        assert!(aligned.abs() < 1e-5, "{aligned}");
+        let diagonal = error(Vec2::new(1., 0.9)).expect("Aim error");
+        assert!((diagonal - 0.9_f32.atan()).abs() < 1e-5, "{diagonal}");
+        // Aiming away from every target
+        let away = error(Vec2::new(-1., -1.)).expect("Aim error");
+        assert!(
+            (away - 3. * std::f32::consts::FRAC_PI_4).abs() < 1e-5,
+            "{away}"
+        );
+
+        // Nothing to aim at
+        assert_eq!(aim_error(Vec2::ZERO, Vec2::X, std::iter::empty()), None);
+        assert_eq!(error(Vec2::ZERO), None);
+    }
+}
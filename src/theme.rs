@@ -0,0 +1,40 @@
+//! Colors shared across the game, so that the features do not hardcode their own.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, InspectorPlugin};
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InspectorPlugin::<Theme>::new())
+            .add_system(update_clear_color);
+    }
+}
+
+#[derive(Inspectable)]
+pub struct Theme {
+    /// Color of the window behind the arena, updated live.
+    pub background: Color,
+    /// Color of the edges of the arena, read when they are spawned at startup.
+    pub wall: Color,
+    /// Color highlighting the displays, such as the flash of the impulse once ready.
+    pub accent: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: Color::BLACK,
+            wall: Color::rgb(1., 0.8, 0.4),
+            accent: Color::WHITE,
+        }
+    }
+}
+
+/// Clear the window with the background of the theme, including when it is first added.
+fn update_clear_color(theme: Res<Theme>, mut clear_color: ResMut<ClearColor>) {
+    if theme.is_changed() {
+        clear_color.0 = theme.background;
+    }
+}
@@ -0,0 +1,147 @@
+//! Time trial: checkpoints to go through in order, as fast as possible.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::{
+    bevy_egui::{egui, EguiContext},
+    Inspectable, InspectorPlugin,
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::{contacts::GameplayCollision, menu::gameplay_running, Arena, Player, Z};
+
+const CHECKPOINT_RADIUS: f32 = 25.;
+/// Checkpoints placed on a circle in the arena, starting from the right.
+const CHECKPOINT_COUNT: usize = 4;
+const NEXT_CHECKPOINT_COLOR: Color = Color::LIME_GREEN;
+const CHECKPOINT_COLOR: Color = Color::DARK_GRAY;
+
+pub struct TimeTrialPlugin;
+
+impl Plugin for TimeTrialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InspectorPlugin::<TimeTrialSettings>::new())
+            .init_resource::<TimeTrial>()
+            .add_startup_system(setup_checkpoints)
+            .add_system(track_checkpoints.with_run_criteria(gameplay_running))
+            .add_system(light_checkpoints.after(track_checkpoints))
+            .add_system(show_time_trial);
+    }
+}
+
+/// Read at startup, when the checkpoints are placed.
+#[derive(Inspectable, Default)]
+pub struct TimeTrialSettings {
+    enabled: bool,
+}
+
+/// Progress through the checkpoints.
+#[derive(Default)]
+pub struct TimeTrial {
+    /// Index of the checkpoint to go through next.
+    next: usize,
+    /// Time since the first checkpoint was crossed, not counting the pauses.
+    elapsed: Option<f64>,
+    /// Duration of the last completed trial, in seconds.
+    last: Option<f64>,
+}
+
+/// Sensor with its index in the order of the trial.
+#[derive(Component)]
+struct Checkpoint(usize);
+
+fn setup_checkpoints(mut commands: Commands, settings: Res<TimeTrialSettings>, arena: Res<Arena>) {
+    if !settings.enabled {
+        return;
+    }
+    let radius = arena.half_size * 0.7;
+
+    for index in 0..CHECKPOINT_COUNT {
+        #[allow(clippy::cast_precision_loss)] // A handful of checkpoints
+        let angle = std::f32::consts::TAU * index as f32 / CHECKPOINT_COUNT as f32;
+        let position = Vec2::new(angle.cos(), angle.sin()) * radius;
+
+        commands
+            .spawn()
+            .insert(Name::new("Checkpoint"))
+            .insert(Checkpoint(index))
+            .insert_bundle((
+                Collider::ball(CHECKPOINT_RADIUS),
+                Sensor,
+                ColliderDebugColor(CHECKPOINT_COLOR),
+            ))
+            .insert_bundle(TransformBundle::from(Transform::from_xyz(
+                position.x, position.y, Z,
+            )));
+    }
+}
+
+/// Advance the trial when the player goes through the next checkpoint.
+fn track_checkpoints(
+    time: Res<Time>,
+    mut trial: ResMut<TimeTrial>,
+    mut collisions: EventReader<GameplayCollision>,
+    players: Query<(), With<Player>>,
+    checkpoints: Query<&Checkpoint>,
+) {
+    if let Some(elapsed) = &mut trial.elapsed {
+        *elapsed += time.delta_seconds_f64();
+    }
+
+    for collision in collisions.iter() {
+        if !collision.started || !collision.entities.iter().any(|e| players.contains(*e)) {
+            continue;
+        }
+        let crossed = collision
+            .entities
+            .iter()
+            .find_map(|entity| checkpoints.get(*entity).ok());
+        if !matches!(crossed, Some(checkpoint) if checkpoint.0 == trial.next) {
+            continue;
+        }
+
+        if trial.next == 0 {
+            trial.elapsed = Some(0.);
+        }
+        trial.next += 1;
+
+        if trial.next == CHECKPOINT_COUNT {
+            trial.last = trial.elapsed.take();
+            trial.next = 0;
+        }
+    }
+}
+
+fn light_checkpoints(
+    trial: Res<TimeTrial>,
+    mut checkpoints: Query<(&Checkpoint, &mut ColliderDebugColor)>,
+) {
+    if !trial.is_changed() {
+        return;
+    }
+    for (checkpoint, mut debug_color) in &mut checkpoints {
+        debug_color.0 = if checkpoint.0 == trial.next {
+            NEXT_CHECKPOINT_COLOR
+        } else {
+            CHECKPOINT_COLOR
+        };
+    }
+}
+
+fn show_time_trial(
+    mut egui_context: ResMut<EguiContext>,
+    settings: Res<TimeTrialSettings>,
+    trial: Res<TimeTrial>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    egui::Window::new("Time trial").show(egui_context.ctx_mut(), |ui| {
+        ui.label(format!("Checkpoint {}/{CHECKPOINT_COUNT}", trial.next + 1));
+        if let Some(elapsed) = trial.elapsed {
+            ui.label(format!("Time {elapsed:.2}s"));
+        }
+        if let Some(last) = trial.last {
+            ui.label(format!("Last {last:.2}s"));
+        }
+    });
+}
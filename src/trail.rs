@@ -0,0 +1,138 @@
+//! Fading trail following the player.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{heat_color, Constants, Heat, Player, PLAYER_RADIUS, Z};
+
+const TRAIL_COLOR: Color = Color::ORANGE;
+
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(ShapePlugin)
+            .add_startup_system(setup_trail)
+            .add_system(record_trail_points)
+            .add_system(update_trail_segments.after(record_trail_points));
+    }
+}
+
+/// Opacity of the newest segment of the trail.
+const TRAIL_ALPHA: f32 = 0.5;
+/// Width of the glow of the trail, relative to its segments.
+const GLOW_WIDTH_SCALE: f32 = 3.;
+/// Opacity of the glow of the trail, relative to its segments.
+const GLOW_ALPHA_SCALE: f32 = 0.3;
+
+/// Recent points of an entity, the newest first.
+#[derive(Component, Default)]
+pub struct TrailPoints(VecDeque<TrailPoint>);
+
+/// Position of an entity, with its heat at the time.
+#[derive(Clone, Copy)]
+struct TrailPoint {
+    position: Vec2,
+    /// Zero for the entities without heat.
+    heat: f32,
+}
+
+/// Line of the trail between the points at `index` and `index + 1`.
+#[derive(Component)]
+struct TrailSegment {
+    index: usize,
+    /// Wider and fainter line behind the segment, shown with `Constants::line_glow`.
+    glow: bool,
+}
+
+/// Stroke of the lines, with the caps chosen in the `Constants`.
+pub fn line_stroke(constants: &Constants, color: Color, width: f32) -> DrawMode {
+    let cap = if constants.round_line_caps {
+        LineCap::Round
+    } else {
+        LineCap::Butt
+    };
+    DrawMode::Stroke(StrokeMode {
+        options: StrokeOptions::default()
+            .with_line_width(width)
+            .with_line_cap(cap),
+        color,
+    })
+}
+
+/// Spawn the segments of the trail, their number is fixed at startup.
+fn setup_trail(mut commands: Commands, constants: Res<Constants>) {
+    let width = PLAYER_RADIUS * constants.trail_size_scale;
+
+    for index in 0..constants.trail_length.saturating_sub(1) {
+        for (glow, name, z) in [(false, "Trail segment", Z), (true, "Trail glow", Z - 0.1)] {
+            commands
+                .spawn_bundle(GeometryBuilder::build_as(
+                    &shapes::Line(Vec2::ZERO, Vec2::ZERO),
+                    line_stroke(&constants, TRAIL_COLOR, width),
+                    Transform::from_xyz(0., 0., z),
+                ))
+                .insert(Name::new(name))
+                .insert(TrailSegment { index, glow });
+        }
+    }
+}
+
+fn record_trail_points(
+    constants: Res<Constants>,
+    mut trails: Query<(&Transform, Option<&Heat>, &mut TrailPoints)>,
+) {
+    for (transform, heat, mut points) in &mut trails {
+        points.0.push_front(TrailPoint {
+            position: transform.translation.truncate(),
+            heat: heat.map_or(0., |heat| heat.amount),
+        });
+        points.0.truncate(constants.trail_length);
+    }
+}
+
+/// Rebuild the segments between the trail points, fading out the older ones.
+///
+/// In the comet mode, each segment takes the color of the heat at its newest point,
+/// and is as opaque as the player was hot. The glow follows its segment, wider and fainter.
+#[allow(clippy::cast_precision_loss)] // The trail is a few dozen points long
+fn update_trail_segments(
+    constants: Res<Constants>,
+    player: Query<&TrailPoints, With<Player>>,
+    mut segments: Query<(&TrailSegment, &mut Path, &mut DrawMode, &mut Visibility)>,
+) {
+    let points = match player.get_single() {
+        Ok(trail_points) => &trail_points.0,
+        Err(_) => return,
+    };
+    let width = PLAYER_RADIUS * constants.trail_size_scale;
+
+    for (segment, mut path, mut draw_mode, mut visibility) in &mut segments {
+        let (start, end) = match (points.get(segment.index), points.get(segment.index + 1)) {
+            (Some(start), Some(end)) if constants.line_glow || !segment.glow => (start, end),
+            _ => {
+                visibility.is_visible = false;
+                continue;
+            }
+        };
+        visibility.is_visible = true;
+
+        let fade = TRAIL_ALPHA * (1. - segment.index as f32 / points.len() as f32);
+        let (mut color, alpha) = if constants.comet_trail {
+            (heat_color(start.heat, &constants), fade * start.heat)
+        } else {
+            (TRAIL_COLOR, fade)
+        };
+        let (width, alpha) = if segment.glow {
+            (width * GLOW_WIDTH_SCALE, alpha * GLOW_ALPHA_SCALE)
+        } else {
+            (width, alpha)
+        };
+        color.set_a(alpha);
+
+        *path = ShapePath::build_as(&shapes::Line(start.position, end.position));
+        *draw_mode = line_stroke(&constants, color, width);
+    }
+}
@@ -0,0 +1,86 @@
+//! Heat vent: the player cools down quickly, but cannot launch impulses meanwhile.
+
+use bevy::prelude::*;
+
+use crate::{
+    apply_forces,
+    cooldown::Cooldown,
+    freeze_heat,
+    inputs::{translate_actions, InputEvent},
+    menu::gameplay_running,
+    Constants, Heat, Player,
+};
+
+pub struct VentPlugin;
+
+impl Plugin for VentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Venting>()
+            // Locking out the impulses of the same frame
+            .add_system(
+                start_vent
+                    .with_run_criteria(gameplay_running)
+                    .after(translate_actions)
+                    .before(apply_forces),
+            )
+            .add_system(
+                vent_heat
+                    .with_run_criteria(gameplay_running)
+                    .after(start_vent)
+                    .before(freeze_heat),
+            );
+    }
+}
+
+/// Cooling of the player, for `Constants::vent_duration`.
+pub struct Venting {
+    pub active: bool,
+    duration: Cooldown,
+}
+
+impl Default for Venting {
+    fn default() -> Self {
+        Self {
+            active: false,
+            // The duration is set from the constants when venting
+            duration: Cooldown::from_seconds(0.),
+        }
+    }
+}
+
+fn start_vent(
+    constants: Res<Constants>,
+    mut input_events: EventReader<InputEvent>,
+    mut venting: ResMut<Venting>,
+) {
+    for input_event in input_events.iter() {
+        if !matches!(input_event, InputEvent::Vent) || venting.active {
+            continue;
+        }
+        venting.active = true;
+        venting.duration.set_duration(constants.vent_duration);
+        venting.duration.start();
+    }
+}
+
+/// Cool the player at `Constants::vent_rate` until the vent is over.
+fn vent_heat(
+    constants: Res<Constants>,
+    time: Res<Time>,
+    mut venting: ResMut<Venting>,
+    mut player: Query<&mut Heat, With<Player>>,
+) {
+    if !venting.active {
+        return;
+    }
+    for mut heat in &mut player {
+        if heat.amount > 0. || heat.pending > 0. {
+            heat.inc(-constants.vent_rate * time.delta_seconds());
+        }
+    }
+
+    venting.duration.tick(time.delta());
+    if venting.duration.finished() {
+        venting.active = false;
+    }
+}